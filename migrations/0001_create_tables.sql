@@ -0,0 +1,74 @@
+-- Tables as originally created by setup_tables; IF NOT EXISTS so databases
+-- created before migrations existed are adopted as-is
+CREATE TABLE IF NOT EXISTS token_launches (
+    id SERIAL PRIMARY KEY,
+    signature TEXT UNIQUE NOT NULL,
+    trader_public_key TEXT NOT NULL,
+    tx_type TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    sol_in_pool DOUBLE PRECISION,
+    tokens_in_pool DOUBLE PRECISION,
+    initial_buy DOUBLE PRECISION NOT NULL,
+    sol_amount DOUBLE PRECISION NOT NULL,
+    new_token_balance DOUBLE PRECISION,
+    market_cap_sol DOUBLE PRECISION NOT NULL,
+    name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    uri TEXT NOT NULL,
+    pool TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS trades (
+    id SERIAL PRIMARY KEY,
+    signature TEXT UNIQUE NOT NULL,
+    mint TEXT NOT NULL,
+    trader_public_key TEXT NOT NULL,
+    tx_type TEXT NOT NULL,
+    token_amount DOUBLE PRECISION NOT NULL,
+    sol_amount DOUBLE PRECISION NOT NULL,
+    new_token_balance DOUBLE PRECISION,
+    bonding_curve_key TEXT,
+    v_tokens_in_bonding_curve DOUBLE PRECISION,
+    v_sol_in_bonding_curve DOUBLE PRECISION,
+    market_cap_sol DOUBLE PRECISION NOT NULL,
+    pool TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS wallet_trades (
+    id SERIAL PRIMARY KEY,
+    watched_wallet TEXT NOT NULL,
+    signature TEXT UNIQUE NOT NULL,
+    mint TEXT NOT NULL,
+    trader_public_key TEXT NOT NULL,
+    tx_type TEXT NOT NULL,
+    token_amount DOUBLE PRECISION NOT NULL,
+    sol_amount DOUBLE PRECISION NOT NULL,
+    new_token_balance DOUBLE PRECISION,
+    bonding_curve_key TEXT,
+    v_tokens_in_bonding_curve DOUBLE PRECISION,
+    v_sol_in_bonding_curve DOUBLE PRECISION,
+    market_cap_sol DOUBLE PRECISION NOT NULL,
+    pool TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+-- Bonding curve graduations
+CREATE TABLE IF NOT EXISTS migrations (
+    id SERIAL PRIMARY KEY,
+    signature TEXT UNIQUE NOT NULL,
+    mint TEXT NOT NULL,
+    tx_type TEXT NOT NULL,
+    pool TEXT,
+    created_at TIMESTAMP DEFAULT NOW()
+);
+
+-- Unparseable and unknown messages
+CREATE TABLE IF NOT EXISTS dead_letters (
+    id SERIAL PRIMARY KEY,
+    received_at TIMESTAMPTZ NOT NULL,
+    reason TEXT NOT NULL,
+    raw TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW()
+);
@@ -0,0 +1,13 @@
+-- Columns the feed can omit were NOT NULL in older databases
+ALTER TABLE token_launches ALTER COLUMN sol_in_pool DROP NOT NULL;
+ALTER TABLE token_launches ALTER COLUMN tokens_in_pool DROP NOT NULL;
+ALTER TABLE token_launches ALTER COLUMN new_token_balance DROP NOT NULL;
+ALTER TABLE trades ALTER COLUMN new_token_balance DROP NOT NULL;
+ALTER TABLE trades ALTER COLUMN bonding_curve_key DROP NOT NULL;
+ALTER TABLE trades ALTER COLUMN v_tokens_in_bonding_curve DROP NOT NULL;
+ALTER TABLE trades ALTER COLUMN v_sol_in_bonding_curve DROP NOT NULL;
+ALTER TABLE wallet_trades ALTER COLUMN new_token_balance DROP NOT NULL;
+ALTER TABLE wallet_trades ALTER COLUMN bonding_curve_key DROP NOT NULL;
+ALTER TABLE wallet_trades ALTER COLUMN v_tokens_in_bonding_curve DROP NOT NULL;
+ALTER TABLE wallet_trades ALTER COLUMN v_sol_in_bonding_curve DROP NOT NULL;
+ALTER TABLE migrations ALTER COLUMN pool DROP NOT NULL;
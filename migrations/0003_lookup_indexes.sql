@@ -0,0 +1,17 @@
+-- Per-token and per-trader lookups, and time range scans
+CREATE INDEX IF NOT EXISTS token_launches_mint_idx ON token_launches (mint);
+CREATE INDEX IF NOT EXISTS token_launches_trader_public_key_idx ON token_launches (trader_public_key);
+CREATE INDEX IF NOT EXISTS token_launches_created_at_idx ON token_launches (created_at);
+
+CREATE INDEX IF NOT EXISTS trades_mint_idx ON trades (mint);
+CREATE INDEX IF NOT EXISTS trades_trader_public_key_idx ON trades (trader_public_key);
+CREATE INDEX IF NOT EXISTS trades_created_at_idx ON trades (created_at);
+
+CREATE INDEX IF NOT EXISTS wallet_trades_mint_idx ON wallet_trades (mint);
+CREATE INDEX IF NOT EXISTS wallet_trades_trader_public_key_idx ON wallet_trades (trader_public_key);
+CREATE INDEX IF NOT EXISTS wallet_trades_created_at_idx ON wallet_trades (created_at);
+
+CREATE INDEX IF NOT EXISTS migrations_mint_idx ON migrations (mint);
+CREATE INDEX IF NOT EXISTS migrations_created_at_idx ON migrations (created_at);
+
+CREATE INDEX IF NOT EXISTS dead_letters_received_at_idx ON dead_letters (received_at);
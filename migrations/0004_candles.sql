@@ -0,0 +1,22 @@
+-- Per-mint OHLCV candles built by the pipeline; prices are SOL per token
+CREATE TABLE IF NOT EXISTS candles (
+    mint TEXT NOT NULL,
+    interval_secs BIGINT NOT NULL,
+    open_time TIMESTAMPTZ NOT NULL,
+    close_time TIMESTAMPTZ NOT NULL,
+    open DOUBLE PRECISION NOT NULL,
+    high DOUBLE PRECISION NOT NULL,
+    low DOUBLE PRECISION NOT NULL,
+    close DOUBLE PRECISION NOT NULL,
+    volume_sol DOUBLE PRECISION NOT NULL,
+    volume_tokens DOUBLE PRECISION NOT NULL,
+    buy_volume_sol DOUBLE PRECISION NOT NULL,
+    sell_volume_sol DOUBLE PRECISION NOT NULL,
+    trade_count BIGINT NOT NULL,
+    buy_count BIGINT NOT NULL,
+    sell_count BIGINT NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    PRIMARY KEY (mint, interval_secs, open_time)
+);
+
+CREATE INDEX IF NOT EXISTS candles_interval_open_time_idx ON candles (interval_secs, open_time);
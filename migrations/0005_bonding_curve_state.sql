@@ -0,0 +1,31 @@
+-- Columns derived from each trade's bonding curve reserves
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS spot_price DOUBLE PRECISION;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS real_sol_reserves DOUBLE PRECISION;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS graduation_progress_pct DOUBLE PRECISION;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS curve_status TEXT;
+
+ALTER TABLE wallet_trades ADD COLUMN IF NOT EXISTS spot_price DOUBLE PRECISION;
+ALTER TABLE wallet_trades ADD COLUMN IF NOT EXISTS real_sol_reserves DOUBLE PRECISION;
+ALTER TABLE wallet_trades ADD COLUMN IF NOT EXISTS graduation_progress_pct DOUBLE PRECISION;
+ALTER TABLE wallet_trades ADD COLUMN IF NOT EXISTS curve_status TEXT;
+
+-- Latest known curve state per mint
+CREATE TABLE IF NOT EXISTS bonding_curves (
+    mint TEXT PRIMARY KEY,
+    pool TEXT NOT NULL,
+    v_sol_in_bonding_curve DOUBLE PRECISION NOT NULL,
+    v_tokens_in_bonding_curve DOUBLE PRECISION NOT NULL,
+    market_cap_sol DOUBLE PRECISION NOT NULL,
+    spot_price DOUBLE PRECISION NOT NULL,
+    real_sol_reserves DOUBLE PRECISION NOT NULL,
+    graduation_progress_pct DOUBLE PRECISION,
+    graduated BOOLEAN NOT NULL,
+    last_signature TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    trades BIGINT NOT NULL,
+    gaps BIGINT NOT NULL,
+    out_of_order BIGINT NOT NULL,
+    inconsistent BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS bonding_curves_graduation_progress_idx ON bonding_curves (graduation_progress_pct);
@@ -0,0 +1,22 @@
+-- When and in which session each event was received; created_at is insert time
+ALTER TABLE token_launches ADD COLUMN IF NOT EXISTS received_at TIMESTAMPTZ;
+ALTER TABLE token_launches ADD COLUMN IF NOT EXISTS sequence BIGINT;
+ALTER TABLE token_launches ADD COLUMN IF NOT EXISTS session_id UUID;
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS received_at TIMESTAMPTZ;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS sequence BIGINT;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS session_id UUID;
+
+ALTER TABLE wallet_trades ADD COLUMN IF NOT EXISTS received_at TIMESTAMPTZ;
+ALTER TABLE wallet_trades ADD COLUMN IF NOT EXISTS sequence BIGINT;
+ALTER TABLE wallet_trades ADD COLUMN IF NOT EXISTS session_id UUID;
+
+ALTER TABLE migrations ADD COLUMN IF NOT EXISTS received_at TIMESTAMPTZ;
+ALTER TABLE migrations ADD COLUMN IF NOT EXISTS sequence BIGINT;
+ALTER TABLE migrations ADD COLUMN IF NOT EXISTS session_id UUID;
+
+CREATE INDEX IF NOT EXISTS token_launches_received_at_idx ON token_launches (received_at);
+CREATE INDEX IF NOT EXISTS trades_received_at_idx ON trades (received_at);
+CREATE INDEX IF NOT EXISTS trades_session_sequence_idx ON trades (session_id, sequence);
+CREATE INDEX IF NOT EXISTS wallet_trades_received_at_idx ON wallet_trades (received_at);
+CREATE INDEX IF NOT EXISTS migrations_received_at_idx ON migrations (received_at);
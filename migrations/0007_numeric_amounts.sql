@@ -0,0 +1,46 @@
+-- Exact amounts: SOL as NUMERIC(20, 9) (lamports), tokens as NUMERIC(24, 6) (base units).
+-- The TimescaleDB OHLCV aggregates read these columns, so they are dropped here and
+-- recreated by the TimescaleDB setup that runs after migrations. Compressed chunks
+-- must be decompressed before this migration can alter them.
+DROP MATERIALIZED VIEW IF EXISTS trades_ohlcv_1s;
+DROP MATERIALIZED VIEW IF EXISTS trades_ohlcv_1m;
+DROP MATERIALIZED VIEW IF EXISTS trades_ohlcv_5m;
+DROP MATERIALIZED VIEW IF EXISTS trades_ohlcv_1h;
+
+ALTER TABLE token_launches
+    ALTER COLUMN sol_in_pool TYPE NUMERIC(20, 9),
+    ALTER COLUMN tokens_in_pool TYPE NUMERIC(24, 6),
+    ALTER COLUMN initial_buy TYPE NUMERIC(24, 6),
+    ALTER COLUMN sol_amount TYPE NUMERIC(20, 9),
+    ALTER COLUMN new_token_balance TYPE NUMERIC(24, 6),
+    ALTER COLUMN market_cap_sol TYPE NUMERIC(20, 9);
+
+ALTER TABLE trades
+    ALTER COLUMN token_amount TYPE NUMERIC(24, 6),
+    ALTER COLUMN sol_amount TYPE NUMERIC(20, 9),
+    ALTER COLUMN new_token_balance TYPE NUMERIC(24, 6),
+    ALTER COLUMN v_tokens_in_bonding_curve TYPE NUMERIC(24, 6),
+    ALTER COLUMN v_sol_in_bonding_curve TYPE NUMERIC(20, 9),
+    ALTER COLUMN market_cap_sol TYPE NUMERIC(20, 9),
+    ALTER COLUMN real_sol_reserves TYPE NUMERIC(20, 9);
+
+ALTER TABLE wallet_trades
+    ALTER COLUMN token_amount TYPE NUMERIC(24, 6),
+    ALTER COLUMN sol_amount TYPE NUMERIC(20, 9),
+    ALTER COLUMN new_token_balance TYPE NUMERIC(24, 6),
+    ALTER COLUMN v_tokens_in_bonding_curve TYPE NUMERIC(24, 6),
+    ALTER COLUMN v_sol_in_bonding_curve TYPE NUMERIC(20, 9),
+    ALTER COLUMN market_cap_sol TYPE NUMERIC(20, 9),
+    ALTER COLUMN real_sol_reserves TYPE NUMERIC(20, 9);
+
+ALTER TABLE candles
+    ALTER COLUMN volume_sol TYPE NUMERIC(20, 9),
+    ALTER COLUMN volume_tokens TYPE NUMERIC(24, 6),
+    ALTER COLUMN buy_volume_sol TYPE NUMERIC(20, 9),
+    ALTER COLUMN sell_volume_sol TYPE NUMERIC(20, 9);
+
+ALTER TABLE bonding_curves
+    ALTER COLUMN v_sol_in_bonding_curve TYPE NUMERIC(20, 9),
+    ALTER COLUMN v_tokens_in_bonding_curve TYPE NUMERIC(24, 6),
+    ALTER COLUMN market_cap_sol TYPE NUMERIC(20, 9),
+    ALTER COLUMN real_sol_reserves TYPE NUMERIC(20, 9);
@@ -0,0 +1,4 @@
+-- Receive times of a candle's first and last trade, so a candle split by a restart
+-- can be merged while a replay of the same trades still replaces it
+ALTER TABLE candles ADD COLUMN IF NOT EXISTS first_trade_at TIMESTAMPTZ;
+ALTER TABLE candles ADD COLUMN IF NOT EXISTS last_trade_at TIMESTAMPTZ;
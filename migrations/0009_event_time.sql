@@ -0,0 +1,25 @@
+-- received_at becomes the event time that TimescaleDB partitions and buckets on;
+-- rows from before receipt metadata fall back to their insert time. Compressed
+-- chunks must be decompressed before this migration can update them.
+UPDATE token_launches SET received_at = COALESCE(created_at::timestamptz, NOW()) WHERE received_at IS NULL;
+ALTER TABLE token_launches
+    ALTER COLUMN received_at SET DEFAULT NOW(),
+    ALTER COLUMN received_at SET NOT NULL;
+
+UPDATE trades SET received_at = COALESCE(created_at::timestamptz, NOW()) WHERE received_at IS NULL;
+ALTER TABLE trades
+    ALTER COLUMN received_at SET DEFAULT NOW(),
+    ALTER COLUMN received_at SET NOT NULL;
+
+-- Every signature inserted so far. Hypertables can only enforce uniqueness together
+-- with their time column, so inserts dedup against these instead
+CREATE TABLE IF NOT EXISTS token_launch_signatures (signature TEXT PRIMARY KEY);
+CREATE TABLE IF NOT EXISTS trade_signatures (signature TEXT PRIMARY KEY);
+INSERT INTO token_launch_signatures SELECT signature FROM token_launches ON CONFLICT DO NOTHING;
+INSERT INTO trade_signatures SELECT signature FROM trades ON CONFLICT DO NOTHING;
+
+-- Recreated on received_at by the TimescaleDB setup that runs after migrations
+DROP MATERIALIZED VIEW IF EXISTS trades_ohlcv_1s;
+DROP MATERIALIZED VIEW IF EXISTS trades_ohlcv_1m;
+DROP MATERIALIZED VIEW IF EXISTS trades_ohlcv_5m;
+DROP MATERIALIZED VIEW IF EXISTS trades_ohlcv_1h;
@@ -0,0 +1,12 @@
+-- When each signature was first seen, so the signatures tables can be pruned
+-- alongside TimescaleDB retention instead of growing forever
+ALTER TABLE token_launch_signatures ADD COLUMN IF NOT EXISTS received_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE trade_signatures ADD COLUMN IF NOT EXISTS received_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+UPDATE token_launch_signatures s SET received_at = t.received_at
+FROM token_launches t WHERE t.signature = s.signature;
+UPDATE trade_signatures s SET received_at = t.received_at
+FROM trades t WHERE t.signature = s.signature;
+
+CREATE INDEX IF NOT EXISTS token_launch_signatures_received_at_idx ON token_launch_signatures (received_at);
+CREATE INDEX IF NOT EXISTS trade_signatures_received_at_idx ON trade_signatures (received_at);
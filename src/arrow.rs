@@ -1,128 +1,640 @@
-use arrow::datatypes::{DataType, Field, Schema};
-use arrow::array::{Float64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Int16Type, Schema, SchemaRef, TimeUnit};
+use arrow::array::{
+    ArrayBuilder, ArrayRef, Decimal128Builder, DictionaryArray, Float64Builder, Int64Builder, StringBuilder,
+    StringDictionaryBuilder, TimestampMicrosecondBuilder,
+};
 use arrow::record_batch::RecordBatch;
-use arrow::error::Result;
+use arrow::error::{ArrowError, Result};
+use std::collections::HashSet;
 use std::sync::Arc;
 
-use crate::process_data::PumpEvent;
+use crate::event::{Candle, EventMeta, Migration, PumpEvent, TokenLaunch, Trade, WalletTrade};
+
+// Bumped whenever a dataset's columns or types change; stored in every Parquet file
+pub const SCHEMA_VERSION: u32 = 4;
+pub const SCHEMA_VERSION_KEY: &str = "pumptrace.schema_version";
+// Event type of the rows in a Parquet file
+pub const DATASET_KEY: &str = "pumptrace.dataset";
+
+// SOL amounts in lamports, token amounts in 6-decimal base units
+pub const SOL_PRECISION: u8 = 20;
+pub const SOL_SCALE: i8 = 9;
+pub const TOKEN_PRECISION: u8 = 24;
+pub const TOKEN_SCALE: i8 = 6;
+
+// The feed sends amounts as JSON floats; round them to whole base units
+pub fn to_base_units(value: f64, scale: i8) -> i128 {
+    (value * 10f64.powi(scale as i32)).round() as i128
+}
+
+fn sol_type() -> DataType {
+    DataType::Decimal128(SOL_PRECISION, SOL_SCALE)
+}
+
+fn token_type() -> DataType {
+    DataType::Decimal128(TOKEN_PRECISION, TOKEN_SCALE)
+}
+
+// Enum-like strings with a handful of distinct values
+fn dictionary_type() -> DataType {
+    DataType::Dictionary(Box::new(DataType::Int16), Box::new(DataType::Utf8))
+}
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+}
+
+// Receipt metadata columns, appended to every feed event schema
+fn with_meta(mut fields: Vec<Field>) -> Schema {
+    fields.extend([
+        Field::new("received_at", timestamp_type(), true),
+        Field::new("sequence", DataType::Int64, true),
+        Field::new("session_id", DataType::Utf8, true),
+    ]);
+    Schema::new(fields)
+}
 
 pub fn launch_schema() -> Schema {
-    Schema::new(vec![  // Schema::new, not Schema::from
+    with_meta(vec![
         Field::new("signature", DataType::Utf8, false),
         Field::new("trader_public_key", DataType::Utf8, false),
-        Field::new("tx_type", DataType::Utf8, false),
+        Field::new("tx_type", dictionary_type(), false),
         Field::new("mint", DataType::Utf8, false),
-        Field::new("sol_in_pool", DataType::Float64, false),
-        Field::new("tokens_in_pool", DataType::Float64, false),
-        Field::new("initial_buy", DataType::Float64, false),
-        Field::new("sol_amount", DataType::Float64, false),
-        Field::new("new_token_balance", DataType::Float64, false),
-        Field::new("market_cap_sol", DataType::Float64, false),
+        Field::new("sol_in_pool", sol_type(), true),
+        Field::new("tokens_in_pool", token_type(), true),
+        Field::new("initial_buy", token_type(), false),
+        Field::new("sol_amount", sol_type(), false),
+        Field::new("new_token_balance", token_type(), true),
+        Field::new("market_cap_sol", sol_type(), false),
         Field::new("name", DataType::Utf8, false),
         Field::new("symbol", DataType::Utf8, false),
         Field::new("uri", DataType::Utf8, false),
-        Field::new("pool", DataType::Utf8, false),
+        Field::new("pool", dictionary_type(), false),
     ])
 }
 
 pub fn trade_schema() -> Schema {
-    Schema::new(vec![
+    with_meta(vec![
         Field::new("signature", DataType::Utf8, false),
         Field::new("mint", DataType::Utf8, false),
         Field::new("trader_public_key", DataType::Utf8, false),
-        Field::new("tx_type", DataType::Utf8, false),
-        Field::new("token_amount", DataType::Float64, false),
-        Field::new("sol_amount", DataType::Float64, false),
-        Field::new("new_token_balance", DataType::Float64, false),
-        Field::new("bonding_curve_key", DataType::Utf8, false),
-        Field::new("v_tokens_in_bonding_curve", DataType::Float64, false),
-        Field::new("v_sol_in_bonding_curve", DataType::Float64, false),
-        Field::new("market_cap_sol", DataType::Float64, false),
-        Field::new("pool", DataType::Utf8, false),
+        Field::new("tx_type", dictionary_type(), false),
+        Field::new("token_amount", token_type(), false),
+        Field::new("sol_amount", sol_type(), false),
+        Field::new("new_token_balance", token_type(), true),
+        Field::new("bonding_curve_key", DataType::Utf8, true),
+        Field::new("v_tokens_in_bonding_curve", token_type(), true),
+        Field::new("v_sol_in_bonding_curve", sol_type(), true),
+        Field::new("market_cap_sol", sol_type(), false),
+        Field::new("pool", dictionary_type(), false),
+        Field::new("spot_price", DataType::Float64, true),
+        Field::new("real_sol_reserves", sol_type(), true),
+        Field::new("graduation_progress_pct", DataType::Float64, true),
+        Field::new("curve_status", dictionary_type(), true),
+    ])
+}
+
+pub fn wallet_trade_schema() -> Schema {
+    with_meta(vec![
+        Field::new("watched_wallet", DataType::Utf8, false),
+        Field::new("signature", DataType::Utf8, false),
+        Field::new("mint", DataType::Utf8, false),
+        Field::new("trader_public_key", DataType::Utf8, false),
+        Field::new("tx_type", dictionary_type(), false),
+        Field::new("token_amount", token_type(), false),
+        Field::new("sol_amount", sol_type(), false),
+        Field::new("new_token_balance", token_type(), true),
+        Field::new("bonding_curve_key", DataType::Utf8, true),
+        Field::new("v_tokens_in_bonding_curve", token_type(), true),
+        Field::new("v_sol_in_bonding_curve", sol_type(), true),
+        Field::new("market_cap_sol", sol_type(), false),
+        Field::new("pool", dictionary_type(), false),
+        Field::new("spot_price", DataType::Float64, true),
+        Field::new("real_sol_reserves", sol_type(), true),
+        Field::new("graduation_progress_pct", DataType::Float64, true),
+        Field::new("curve_status", dictionary_type(), true),
+    ])
+}
+
+pub fn migration_schema() -> Schema {
+    with_meta(vec![
+        Field::new("signature", DataType::Utf8, false),
+        Field::new("mint", DataType::Utf8, false),
+        Field::new("tx_type", dictionary_type(), false),
+        Field::new("pool", dictionary_type(), true),
+    ])
+}
+
+pub fn candle_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("mint", DataType::Utf8, false),
+        Field::new("interval_secs", DataType::Int64, false),
+        Field::new("open_time", timestamp_type(), false),
+        Field::new("close_time", timestamp_type(), false),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Float64, false),
+        Field::new("volume_sol", sol_type(), false),
+        Field::new("volume_tokens", token_type(), false),
+        Field::new("buy_volume_sol", sol_type(), false),
+        Field::new("sell_volume_sol", sol_type(), false),
+        Field::new("trade_count", DataType::Int64, false),
+        Field::new("buy_count", DataType::Int64, false),
+        Field::new("sell_count", DataType::Int64, false),
+        Field::new("first_trade_at", timestamp_type(), false),
+        Field::new("last_trade_at", timestamp_type(), false),
     ])
 }
 
-// Single event processing with official arrow
-pub fn event_to_record_batch(event: &PumpEvent) -> Result<RecordBatch> {
-    match event {
-        PumpEvent::TokenLaunch {
-            signature,
-            traderPublicKey,
-            txType,
-            mint,
-            solInPool,
-            tokensInPool,
-            initialBuy,
-            solAmount,
-            newTokenBalance,
-            marketCapSol,
-            name,
-            symbol,
-            uri,
-            pool,
-        } => {
-            let schema = Arc::new(launch_schema());
-            
-            RecordBatch::try_new(
-                schema,
-                vec![
-                    Arc::new(StringArray::from(vec![signature.as_str()])),
-                    Arc::new(StringArray::from(vec![traderPublicKey.as_str()])),
-                    Arc::new(StringArray::from(vec![txType.as_str()])),
-                    Arc::new(StringArray::from(vec![mint.as_str()])),
-                    Arc::new(Float64Array::from(vec![*solInPool])),
-                    Arc::new(Float64Array::from(vec![*tokensInPool])),
-                    Arc::new(Float64Array::from(vec![*initialBuy])),
-                    Arc::new(Float64Array::from(vec![*solAmount])),
-                    Arc::new(Float64Array::from(vec![*newTokenBalance])),
-                    Arc::new(Float64Array::from(vec![*marketCapSol])),
-                    Arc::new(StringArray::from(vec![name.as_str()])),
-                    Arc::new(StringArray::from(vec![symbol.as_str()])),
-                    Arc::new(StringArray::from(vec![uri.as_str()])),
-                    Arc::new(StringArray::from(vec![pool.as_str()])),
-                ],
-            )
+// Every Parquet dataset by event type
+pub fn dataset_schemas() -> Vec<(&'static str, Schema)> {
+    vec![
+        ("token_launch", launch_schema()),
+        ("trade", trade_schema()),
+        ("wallet_trade", wallet_trade_schema()),
+        ("migration", migration_schema()),
+        ("candle", candle_schema()),
+    ]
+}
+
+// Column builders for one dataset; events are appended in place and
+// finished into a single RecordBatch per flush
+pub struct EventBatchBuilder {
+    schema: SchemaRef,
+    columns: DatasetColumns,
+}
+
+enum DatasetColumns {
+    TokenLaunch(LaunchColumns),
+    Trade(TradeColumns),
+    WalletTrade(StringBuilder, TradeColumns),
+    Migration(MigrationColumns),
+    Candle(CandleColumns),
+}
+
+impl EventBatchBuilder {
+    // A builder for the event's dataset; Unknown events have no Arrow form
+    pub fn for_event(event: &PumpEvent) -> Option<Self> {
+        let (schema, columns) = match event {
+            PumpEvent::TokenLaunch(_) => (launch_schema(), DatasetColumns::TokenLaunch(LaunchColumns::default())),
+            PumpEvent::Trade(_) => (trade_schema(), DatasetColumns::Trade(TradeColumns::default())),
+            PumpEvent::WalletTrade(_) => (
+                wallet_trade_schema(),
+                DatasetColumns::WalletTrade(StringBuilder::new(), TradeColumns::default()),
+            ),
+            PumpEvent::Migration(_) => (migration_schema(), DatasetColumns::Migration(MigrationColumns::default())),
+            PumpEvent::Candle(_) => (candle_schema(), DatasetColumns::Candle(CandleColumns::default())),
+            PumpEvent::Unknown(_) => return None,
+        };
+        Some(Self {
+            schema: Arc::new(schema),
+            columns,
+        })
+    }
+
+    pub fn event_type(&self) -> &'static str {
+        match &self.columns {
+            DatasetColumns::TokenLaunch(_) => "token_launch",
+            DatasetColumns::Trade(_) => "trade",
+            DatasetColumns::WalletTrade(_, _) => "wallet_trade",
+            DatasetColumns::Migration(_) => "migration",
+            DatasetColumns::Candle(_) => "candle",
         }
-        
-        PumpEvent::Trade {
-            signature,
-            mint,
-            traderPublicKey,
-            txType,
-            tokenAmount,
-            solAmount,
-            newTokenBalance,
-            bondingCurveKey,
-            vTokensInBondingCurve,
-            vSolInBondingCurve,
-            marketCapSol,
-            pool,
-        } => {
-            let schema = Arc::new(trade_schema());
-            
-            RecordBatch::try_new(
-                schema,
-                vec![
-                    Arc::new(StringArray::from(vec![signature.as_str()])),
-                    Arc::new(StringArray::from(vec![mint.as_str()])),
-                    Arc::new(StringArray::from(vec![traderPublicKey.as_str()])),
-                    Arc::new(StringArray::from(vec![txType.as_str()])),
-                    Arc::new(Float64Array::from(vec![*tokenAmount])),
-                    Arc::new(Float64Array::from(vec![*solAmount])),
-                    Arc::new(Float64Array::from(vec![*newTokenBalance])),
-                    Arc::new(StringArray::from(vec![bondingCurveKey.as_str()])),
-                    Arc::new(Float64Array::from(vec![*vTokensInBondingCurve])),
-                    Arc::new(Float64Array::from(vec![*vSolInBondingCurve])),
-                    Arc::new(Float64Array::from(vec![*marketCapSol])),
-                    Arc::new(StringArray::from(vec![pool.as_str()])),
-                ],
-            )
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.columns {
+            DatasetColumns::TokenLaunch(columns) => columns.signature.len(),
+            DatasetColumns::Trade(columns) => columns.signature.len(),
+            DatasetColumns::WalletTrade(watched_wallet, _) => watched_wallet.len(),
+            DatasetColumns::Migration(columns) => columns.signature.len(),
+            DatasetColumns::Candle(columns) => columns.mint.len(),
         }
-        
-        PumpEvent::Unknown => {
-            Err(arrow::error::ArrowError::InvalidArgumentError(
-                "Cannot convert Unknown event type to Arrow format".to_string()
-            ))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn append(&mut self, event: &PumpEvent) -> Result<()> {
+        match (&mut self.columns, event) {
+            (DatasetColumns::TokenLaunch(columns), PumpEvent::TokenLaunch(launch)) => columns.append(launch),
+            (DatasetColumns::Trade(columns), PumpEvent::Trade(trade)) => columns.append(trade),
+            (DatasetColumns::WalletTrade(watched_wallet, columns), PumpEvent::WalletTrade(wallet_trade)) => {
+                append_wallet_trade(watched_wallet, columns, wallet_trade)
+            }
+            (DatasetColumns::Migration(columns), PumpEvent::Migration(migration)) => columns.append(migration),
+            (DatasetColumns::Candle(columns), PumpEvent::Candle(candle)) => columns.append(candle),
+            _ => Err(ArrowError::InvalidArgumentError(format!(
+                "{} event does not belong in this batch",
+                event.event_type()
+            ))),
         }
     }
+
+    // Everything appended so far as one batch; the builder is empty afterwards
+    pub fn finish(&mut self) -> Result<RecordBatch> {
+        let columns = match &mut self.columns {
+            DatasetColumns::TokenLaunch(columns) => columns.finish(),
+            DatasetColumns::Trade(columns) => columns.finish(),
+            DatasetColumns::WalletTrade(watched_wallet, columns) => {
+                let mut arrays: Vec<ArrayRef> = vec![Arc::new(watched_wallet.finish())];
+                arrays.extend(columns.finish());
+                arrays
+            }
+            DatasetColumns::Migration(columns) => columns.finish(),
+            DatasetColumns::Candle(columns) => columns.finish(),
+        };
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+}
+
+fn append_wallet_trade(watched_wallet: &mut StringBuilder, columns: &mut TradeColumns, wallet_trade: &WalletTrade) -> Result<()> {
+    columns.append(&wallet_trade.trade)?;
+    watched_wallet.append_value(&wallet_trade.watchedWallet);
+    Ok(())
+}
+
+fn sol_builder() -> Decimal128Builder {
+    Decimal128Builder::new().with_data_type(sol_type())
+}
+
+fn token_builder() -> Decimal128Builder {
+    Decimal128Builder::new().with_data_type(token_type())
+}
+
+fn append_sol(builder: &mut Decimal128Builder, value: Option<f64>) {
+    builder.append_option(value.map(|value| to_base_units(value, SOL_SCALE)));
+}
+
+fn append_token(builder: &mut Decimal128Builder, value: Option<f64>) {
+    builder.append_option(value.map(|value| to_base_units(value, TOKEN_SCALE)));
+}
+
+// Dictionary column that knows its distinct values, so a row can be checked for room
+// in every dictionary before any of its columns is appended to
+#[derive(Default)]
+struct DictionaryColumn {
+    builder: StringDictionaryBuilder<Int16Type>,
+    values: HashSet<String>,
+}
+
+impl DictionaryColumn {
+    fn check(&self, value: Option<&str>) -> Result<()> {
+        match value {
+            Some(value) if self.values.len() > i16::MAX as usize && !self.values.contains(value) => {
+                Err(ArrowError::DictionaryKeyOverflowError)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Callers check first; append_option panics on a full dictionary
+    fn append(&mut self, value: Option<&str>) {
+        if let Some(value) = value.filter(|value| !self.values.contains(*value)) {
+            self.values.insert(value.to_string());
+        }
+        self.builder.append_option(value);
+    }
+
+    fn finish(&mut self) -> DictionaryArray<Int16Type> {
+        self.values.clear();
+        self.builder.finish()
+    }
+}
+
+// Receipt metadata, the trailing columns of every feed dataset
+struct MetaColumns {
+    received_at: TimestampMicrosecondBuilder,
+    sequence: Int64Builder,
+    session_id: StringBuilder,
+}
+
+impl Default for MetaColumns {
+    fn default() -> Self {
+        Self {
+            received_at: TimestampMicrosecondBuilder::new().with_timezone("UTC"),
+            sequence: Int64Builder::new(),
+            session_id: StringBuilder::new(),
+        }
+    }
+}
+
+impl MetaColumns {
+    fn append(&mut self, meta: Option<EventMeta>) {
+        self.received_at.append_option(meta.map(|meta| meta.receivedAt.timestamp_micros()));
+        self.sequence.append_option(meta.map(|meta| meta.sequence as i64));
+        self.session_id.append_option(meta.map(|meta| meta.sessionId.to_string()));
+    }
+
+    fn finish(&mut self) -> [ArrayRef; 3] {
+        [
+            Arc::new(self.received_at.finish()),
+            Arc::new(self.sequence.finish()),
+            Arc::new(self.session_id.finish()),
+        ]
+    }
+}
+
+struct LaunchColumns {
+    signature: StringBuilder,
+    trader_public_key: StringBuilder,
+    tx_type: DictionaryColumn,
+    mint: StringBuilder,
+    sol_in_pool: Decimal128Builder,
+    tokens_in_pool: Decimal128Builder,
+    initial_buy: Decimal128Builder,
+    sol_amount: Decimal128Builder,
+    new_token_balance: Decimal128Builder,
+    market_cap_sol: Decimal128Builder,
+    name: StringBuilder,
+    symbol: StringBuilder,
+    uri: StringBuilder,
+    pool: DictionaryColumn,
+    meta: MetaColumns,
+}
+
+impl Default for LaunchColumns {
+    fn default() -> Self {
+        Self {
+            signature: StringBuilder::new(),
+            trader_public_key: StringBuilder::new(),
+            tx_type: DictionaryColumn::default(),
+            mint: StringBuilder::new(),
+            sol_in_pool: sol_builder(),
+            tokens_in_pool: token_builder(),
+            initial_buy: token_builder(),
+            sol_amount: sol_builder(),
+            new_token_balance: token_builder(),
+            market_cap_sol: sol_builder(),
+            name: StringBuilder::new(),
+            symbol: StringBuilder::new(),
+            uri: StringBuilder::new(),
+            pool: DictionaryColumn::default(),
+            meta: MetaColumns::default(),
+        }
+    }
+}
+
+impl LaunchColumns {
+    fn append(&mut self, launch: &TokenLaunch) -> Result<()> {
+        // Check the dictionaries before appending so a failure leaves every column the same length
+        self.tx_type.check(Some(&launch.txType))?;
+        self.pool.check(Some(&launch.pool))?;
+        self.tx_type.append(Some(&launch.txType));
+        self.pool.append(Some(&launch.pool));
+        self.signature.append_value(&launch.signature);
+        self.trader_public_key.append_value(&launch.traderPublicKey);
+        self.mint.append_value(&launch.mint);
+        append_sol(&mut self.sol_in_pool, launch.solInPool);
+        append_token(&mut self.tokens_in_pool, launch.tokensInPool);
+        append_token(&mut self.initial_buy, Some(launch.initialBuy));
+        append_sol(&mut self.sol_amount, Some(launch.solAmount));
+        append_token(&mut self.new_token_balance, launch.newTokenBalance);
+        append_sol(&mut self.market_cap_sol, Some(launch.marketCapSol));
+        self.name.append_value(&launch.name);
+        self.symbol.append_value(&launch.symbol);
+        self.uri.append_value(&launch.uri);
+        self.meta.append(launch.meta);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(self.signature.finish()),
+            Arc::new(self.trader_public_key.finish()),
+            Arc::new(self.tx_type.finish()),
+            Arc::new(self.mint.finish()),
+            Arc::new(self.sol_in_pool.finish()),
+            Arc::new(self.tokens_in_pool.finish()),
+            Arc::new(self.initial_buy.finish()),
+            Arc::new(self.sol_amount.finish()),
+            Arc::new(self.new_token_balance.finish()),
+            Arc::new(self.market_cap_sol.finish()),
+            Arc::new(self.name.finish()),
+            Arc::new(self.symbol.finish()),
+            Arc::new(self.uri.finish()),
+            Arc::new(self.pool.finish()),
+        ];
+        columns.extend(self.meta.finish());
+        columns
+    }
+}
+
+// Columns shared by trade_schema and the tail of wallet_trade_schema
+struct TradeColumns {
+    signature: StringBuilder,
+    mint: StringBuilder,
+    trader_public_key: StringBuilder,
+    tx_type: DictionaryColumn,
+    token_amount: Decimal128Builder,
+    sol_amount: Decimal128Builder,
+    new_token_balance: Decimal128Builder,
+    bonding_curve_key: StringBuilder,
+    v_tokens_in_bonding_curve: Decimal128Builder,
+    v_sol_in_bonding_curve: Decimal128Builder,
+    market_cap_sol: Decimal128Builder,
+    pool: DictionaryColumn,
+    spot_price: Float64Builder,
+    real_sol_reserves: Decimal128Builder,
+    graduation_progress_pct: Float64Builder,
+    curve_status: DictionaryColumn,
+    meta: MetaColumns,
+}
+
+impl Default for TradeColumns {
+    fn default() -> Self {
+        Self {
+            signature: StringBuilder::new(),
+            mint: StringBuilder::new(),
+            trader_public_key: StringBuilder::new(),
+            tx_type: DictionaryColumn::default(),
+            token_amount: token_builder(),
+            sol_amount: sol_builder(),
+            new_token_balance: token_builder(),
+            bonding_curve_key: StringBuilder::new(),
+            v_tokens_in_bonding_curve: token_builder(),
+            v_sol_in_bonding_curve: sol_builder(),
+            market_cap_sol: sol_builder(),
+            pool: DictionaryColumn::default(),
+            spot_price: Float64Builder::new(),
+            real_sol_reserves: sol_builder(),
+            graduation_progress_pct: Float64Builder::new(),
+            curve_status: DictionaryColumn::default(),
+            meta: MetaColumns::default(),
+        }
+    }
+}
+
+impl TradeColumns {
+    fn append(&mut self, trade: &Trade) -> Result<()> {
+        let curve_status = trade.curveStatus.map(|status| status.as_str());
+        self.tx_type.check(Some(&trade.txType))?;
+        self.pool.check(Some(&trade.pool))?;
+        self.curve_status.check(curve_status)?;
+        self.tx_type.append(Some(&trade.txType));
+        self.pool.append(Some(&trade.pool));
+        self.curve_status.append(curve_status);
+        self.signature.append_value(&trade.signature);
+        self.mint.append_value(&trade.mint);
+        self.trader_public_key.append_value(&trade.traderPublicKey);
+        append_token(&mut self.token_amount, Some(trade.tokenAmount));
+        append_sol(&mut self.sol_amount, Some(trade.solAmount));
+        append_token(&mut self.new_token_balance, trade.newTokenBalance);
+        self.bonding_curve_key.append_option(trade.bondingCurveKey.as_deref());
+        append_token(&mut self.v_tokens_in_bonding_curve, trade.vTokensInBondingCurve);
+        append_sol(&mut self.v_sol_in_bonding_curve, trade.vSolInBondingCurve);
+        append_sol(&mut self.market_cap_sol, Some(trade.marketCapSol));
+        self.spot_price.append_option(trade.spotPrice);
+        append_sol(&mut self.real_sol_reserves, trade.realSolReserves);
+        self.graduation_progress_pct.append_option(trade.graduationProgressPct);
+        self.meta.append(trade.meta);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(self.signature.finish()),
+            Arc::new(self.mint.finish()),
+            Arc::new(self.trader_public_key.finish()),
+            Arc::new(self.tx_type.finish()),
+            Arc::new(self.token_amount.finish()),
+            Arc::new(self.sol_amount.finish()),
+            Arc::new(self.new_token_balance.finish()),
+            Arc::new(self.bonding_curve_key.finish()),
+            Arc::new(self.v_tokens_in_bonding_curve.finish()),
+            Arc::new(self.v_sol_in_bonding_curve.finish()),
+            Arc::new(self.market_cap_sol.finish()),
+            Arc::new(self.pool.finish()),
+            Arc::new(self.spot_price.finish()),
+            Arc::new(self.real_sol_reserves.finish()),
+            Arc::new(self.graduation_progress_pct.finish()),
+            Arc::new(self.curve_status.finish()),
+        ];
+        columns.extend(self.meta.finish());
+        columns
+    }
+}
+
+#[derive(Default)]
+struct MigrationColumns {
+    signature: StringBuilder,
+    mint: StringBuilder,
+    tx_type: DictionaryColumn,
+    pool: DictionaryColumn,
+    meta: MetaColumns,
+}
+
+impl MigrationColumns {
+    fn append(&mut self, migration: &Migration) -> Result<()> {
+        self.tx_type.check(Some(&migration.txType))?;
+        self.pool.check(migration.pool.as_deref())?;
+        self.tx_type.append(Some(&migration.txType));
+        self.pool.append(migration.pool.as_deref());
+        self.signature.append_value(&migration.signature);
+        self.mint.append_value(&migration.mint);
+        self.meta.append(migration.meta);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(self.signature.finish()),
+            Arc::new(self.mint.finish()),
+            Arc::new(self.tx_type.finish()),
+            Arc::new(self.pool.finish()),
+        ];
+        columns.extend(self.meta.finish());
+        columns
+    }
+}
+
+struct CandleColumns {
+    mint: StringBuilder,
+    interval_secs: Int64Builder,
+    open_time: TimestampMicrosecondBuilder,
+    close_time: TimestampMicrosecondBuilder,
+    open: Float64Builder,
+    high: Float64Builder,
+    low: Float64Builder,
+    close: Float64Builder,
+    volume_sol: Decimal128Builder,
+    volume_tokens: Decimal128Builder,
+    buy_volume_sol: Decimal128Builder,
+    sell_volume_sol: Decimal128Builder,
+    trade_count: Int64Builder,
+    buy_count: Int64Builder,
+    sell_count: Int64Builder,
+    first_trade_at: TimestampMicrosecondBuilder,
+    last_trade_at: TimestampMicrosecondBuilder,
+}
+
+impl Default for CandleColumns {
+    fn default() -> Self {
+        Self {
+            mint: StringBuilder::new(),
+            interval_secs: Int64Builder::new(),
+            open_time: TimestampMicrosecondBuilder::new().with_timezone("UTC"),
+            close_time: TimestampMicrosecondBuilder::new().with_timezone("UTC"),
+            open: Float64Builder::new(),
+            high: Float64Builder::new(),
+            low: Float64Builder::new(),
+            close: Float64Builder::new(),
+            volume_sol: sol_builder(),
+            volume_tokens: token_builder(),
+            buy_volume_sol: sol_builder(),
+            sell_volume_sol: sol_builder(),
+            trade_count: Int64Builder::new(),
+            buy_count: Int64Builder::new(),
+            sell_count: Int64Builder::new(),
+            first_trade_at: TimestampMicrosecondBuilder::new().with_timezone("UTC"),
+            last_trade_at: TimestampMicrosecondBuilder::new().with_timezone("UTC"),
+        }
+    }
+}
+
+impl CandleColumns {
+    fn append(&mut self, candle: &Candle) -> Result<()> {
+        self.mint.append_value(&candle.mint);
+        self.interval_secs.append_value(candle.intervalSecs as i64);
+        self.open_time.append_value(candle.openTime.timestamp_micros());
+        self.close_time.append_value(candle.closeTime.timestamp_micros());
+        self.open.append_value(candle.open);
+        self.high.append_value(candle.high);
+        self.low.append_value(candle.low);
+        self.close.append_value(candle.close);
+        append_sol(&mut self.volume_sol, Some(candle.volumeSol));
+        append_token(&mut self.volume_tokens, Some(candle.volumeTokens));
+        append_sol(&mut self.buy_volume_sol, Some(candle.buyVolumeSol));
+        append_sol(&mut self.sell_volume_sol, Some(candle.sellVolumeSol));
+        self.trade_count.append_value(candle.tradeCount as i64);
+        self.buy_count.append_value(candle.buyCount as i64);
+        self.sell_count.append_value(candle.sellCount as i64);
+        self.first_trade_at.append_value(candle.firstTradeAt.timestamp_micros());
+        self.last_trade_at.append_value(candle.lastTradeAt.timestamp_micros());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.mint.finish()),
+            Arc::new(self.interval_secs.finish()),
+            Arc::new(self.open_time.finish()),
+            Arc::new(self.close_time.finish()),
+            Arc::new(self.open.finish()),
+            Arc::new(self.high.finish()),
+            Arc::new(self.low.finish()),
+            Arc::new(self.close.finish()),
+            Arc::new(self.volume_sol.finish()),
+            Arc::new(self.volume_tokens.finish()),
+            Arc::new(self.buy_volume_sol.finish()),
+            Arc::new(self.sell_volume_sol.finish()),
+            Arc::new(self.trade_count.finish()),
+            Arc::new(self.buy_count.finish()),
+            Arc::new(self.sell_count.finish()),
+            Arc::new(self.first_trade_at.finish()),
+            Arc::new(self.last_trade_at.finish()),
+        ]
+    }
 }
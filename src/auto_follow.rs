@@ -0,0 +1,94 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AutoFollowConfig {
+    pub enabled: bool,
+    pub max_mints: usize,
+    pub ttl_secs: u64,
+}
+
+impl Default for AutoFollowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_mints: 500,
+            ttl_secs: 600,
+        }
+    }
+}
+
+// Mints subscribed dynamically after their launch, keyed by last trade activity
+pub struct AutoFollow {
+    config: AutoFollowConfig,
+    last_seen: HashMap<String, Instant>,
+}
+
+impl AutoFollow {
+    pub fn new(config: AutoFollowConfig) -> Self {
+        Self {
+            config,
+            last_seen: HashMap::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn len(&self) -> usize {
+        self.last_seen.len()
+    }
+
+    // Start following a new mint; returns mints evicted to stay under max_mints, or None
+    // when the mint was already followed (or auto-follow is off) and nothing changed
+    pub fn follow(&mut self, mint: &str, now: Instant) -> Option<Vec<String>> {
+        if !self.config.enabled || self.last_seen.contains_key(mint) {
+            return None;
+        }
+
+        let mut evicted = Vec::new();
+        while self.config.max_mints > 0 && self.last_seen.len() >= self.config.max_mints {
+            let oldest = self
+                .last_seen
+                .iter()
+                .min_by_key(|(_, seen)| **seen)
+                .map(|(mint, _)| mint.clone());
+            match oldest {
+                Some(oldest) => {
+                    self.last_seen.remove(&oldest);
+                    evicted.push(oldest);
+                }
+                None => break,
+            }
+        }
+
+        self.last_seen.insert(mint.to_string(), now);
+        Some(evicted)
+    }
+
+    // Record trade activity so the mint is not evicted for inactivity
+    pub fn touch(&mut self, mint: &str, now: Instant) {
+        if let Some(seen) = self.last_seen.get_mut(mint) {
+            *seen = now;
+        }
+    }
+
+    // Drop mints with no trades within the TTL
+    pub fn evict_expired(&mut self, now: Instant) -> Vec<String> {
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        let expired: Vec<String> = self
+            .last_seen
+            .iter()
+            .filter(|(_, seen)| now.duration_since(**seen) >= ttl)
+            .map(|(mint, _)| mint.clone())
+            .collect();
+
+        for mint in &expired {
+            self.last_seen.remove(mint);
+        }
+        expired
+    }
+}
@@ -0,0 +1,364 @@
+#![allow(non_snake_case)]
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::event::Trade;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BondingCurveConfig {
+    pub enabled: bool,
+    // Write the latest state per mint to <storage>/bonding_curves.json (and Postgres) this often
+    pub snapshot_interval_secs: u64,
+    // Forget mints with no trades for this long
+    pub ttl_secs: u64,
+    // pump.fun curve parameters, used for real reserves and graduation progress
+    pub initial_virtual_sol: f64,
+    pub initial_virtual_tokens: f64,
+    pub initial_real_tokens: f64,
+}
+
+impl Default for BondingCurveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            snapshot_interval_secs: 10,
+            ttl_secs: 3600,
+            initial_virtual_sol: 30.0,
+            initial_virtual_tokens: 1_073_000_000.0,
+            initial_real_tokens: 793_100_000.0,
+        }
+    }
+}
+
+// How a trade's reserves relate to the last state seen for its mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveStatus {
+    // First trade seen for the mint
+    First,
+    // Starts from the last known reserves
+    InOrder,
+    // Trades were missed in between, e.g. before we subscribed
+    Gap,
+    // Starts from reserves we already moved past; not applied
+    OutOfOrder,
+    // Reserves that cannot come from this curve; not applied
+    Inconsistent,
+}
+
+impl CurveStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CurveStatus::First => "first",
+            CurveStatus::InOrder => "in_order",
+            CurveStatus::Gap => "gap",
+            CurveStatus::OutOfOrder => "out_of_order",
+            CurveStatus::Inconsistent => "inconsistent",
+        }
+    }
+
+    fn applies(&self) -> bool {
+        matches!(self, CurveStatus::First | CurveStatus::InOrder | CurveStatus::Gap)
+    }
+}
+
+// Latest known state of one mint's bonding curve
+#[derive(Debug, Clone, Serialize)]
+pub struct BondingCurveState {
+    pub mint: String,
+    pub pool: String,
+    pub vSolInBondingCurve: f64,
+    pub vTokensInBondingCurve: f64,
+    pub marketCapSol: f64,
+    pub spotPrice: f64,
+    pub realSolReserves: f64,
+    pub graduationProgressPct: Option<f64>,
+    pub graduated: bool,
+    pub lastSignature: String,
+    pub updatedAt: DateTime<Utc>,
+    pub trades: u64,
+    pub gaps: u64,
+    pub outOfOrder: u64,
+    pub inconsistent: u64,
+    // Constant product from the first trade; later reserves must stay on this curve
+    #[serde(skip)]
+    k: f64,
+    // Recent post-trade token reserves, newest last, to recognise late and repeated trades
+    #[serde(skip)]
+    recent_tokens: VecDeque<f64>,
+}
+
+const RECENT_STATES: usize = 16;
+// Relative drift allowed in vSol * vTokens before reserves count as inconsistent
+const K_TOLERANCE: f64 = 0.01;
+
+fn approx_eq(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-3 + b.abs() * 1e-9
+}
+
+pub struct BondingCurveTracker {
+    config: BondingCurveConfig,
+    states: HashMap<String, BondingCurveState>,
+    // Newest event time seen; the TTL runs on event time so replays of old journals keep their state
+    latest_at: Option<DateTime<Utc>>,
+}
+
+impl BondingCurveTracker {
+    pub fn new(config: BondingCurveConfig) -> Self {
+        Self {
+            config,
+            states: HashMap::new(),
+            latest_at: None,
+        }
+    }
+
+    // Percent of the curve's sellable tokens already bought; only meaningful for pump.fun curves
+    fn graduation_progress(&self, pool: &str, v_tokens: f64) -> Option<f64> {
+        if pool != "pump" {
+            return None;
+        }
+        let reserved = self.config.initial_virtual_tokens - self.config.initial_real_tokens;
+        let real_tokens_left = v_tokens - reserved;
+        let sold = self.config.initial_real_tokens - real_tokens_left;
+        Some((sold / self.config.initial_real_tokens * 100.0).clamp(0.0, 100.0))
+    }
+
+    fn classify(&self, trade: &Trade, v_sol: f64, v_tokens: f64) -> CurveStatus {
+        if v_sol <= 0.0 || v_tokens <= 0.0 {
+            return CurveStatus::Inconsistent;
+        }
+        let Some(state) = self.states.get(&trade.mint) else {
+            return CurveStatus::First;
+        };
+        if ((v_sol * v_tokens) / state.k - 1.0).abs() > K_TOLERANCE {
+            return CurveStatus::Inconsistent;
+        }
+
+        // Token reserves before this trade: a buy takes tokens out, a sell puts them back
+        let pre_tokens = if trade.txType == "buy" {
+            v_tokens + trade.tokenAmount
+        } else {
+            v_tokens - trade.tokenAmount
+        };
+        let latest = state.vTokensInBondingCurve;
+        if approx_eq(pre_tokens, latest) {
+            return CurveStatus::InOrder;
+        }
+        let seen_before = state
+            .recent_tokens
+            .iter()
+            .any(|tokens| approx_eq(pre_tokens, *tokens) || approx_eq(v_tokens, *tokens));
+        if seen_before {
+            CurveStatus::OutOfOrder
+        } else {
+            CurveStatus::Gap
+        }
+    }
+
+    // Fill the trade's derived curve columns and advance the mint's state
+    pub fn apply_trade(&mut self, trade: &mut Trade, at: DateTime<Utc>) {
+        self.latest_at = Some(self.latest_at.map_or(at, |latest| latest.max(at)));
+
+        // AMM trades carry no bonding curve reserves
+        let (Some(v_sol), Some(v_tokens)) = (trade.vSolInBondingCurve, trade.vTokensInBondingCurve) else {
+            return;
+        };
+
+        let status = self.classify(trade, v_sol, v_tokens);
+        let spot_price = if v_tokens > 0.0 { Some(v_sol / v_tokens) } else { None };
+        let real_sol = v_sol - self.config.initial_virtual_sol;
+        let progress = self.graduation_progress(&trade.pool, v_tokens);
+
+        trade.spotPrice = spot_price;
+        trade.realSolReserves = Some(real_sol);
+        trade.graduationProgressPct = progress;
+        trade.curveStatus = Some(status);
+
+        if status == CurveStatus::First {
+            self.states.insert(
+                trade.mint.clone(),
+                BondingCurveState {
+                    mint: trade.mint.clone(),
+                    pool: trade.pool.clone(),
+                    vSolInBondingCurve: v_sol,
+                    vTokensInBondingCurve: v_tokens,
+                    marketCapSol: trade.marketCapSol,
+                    spotPrice: v_sol / v_tokens,
+                    realSolReserves: real_sol,
+                    graduationProgressPct: progress,
+                    graduated: false,
+                    lastSignature: trade.signature.clone(),
+                    updatedAt: at,
+                    trades: 0,
+                    gaps: 0,
+                    outOfOrder: 0,
+                    inconsistent: 0,
+                    k: v_sol * v_tokens,
+                    recent_tokens: VecDeque::new(),
+                },
+            );
+        }
+
+        // Inconsistent first trades never create a state
+        let Some(state) = self.states.get_mut(&trade.mint) else {
+            return;
+        };
+        state.trades += 1;
+        match status {
+            CurveStatus::Gap => state.gaps += 1,
+            CurveStatus::OutOfOrder => state.outOfOrder += 1,
+            CurveStatus::Inconsistent => state.inconsistent += 1,
+            _ => {}
+        }
+        if !status.applies() {
+            return;
+        }
+
+        if state.recent_tokens.len() == RECENT_STATES {
+            state.recent_tokens.pop_front();
+        }
+        state.recent_tokens.push_back(v_tokens);
+        state.vSolInBondingCurve = v_sol;
+        state.vTokensInBondingCurve = v_tokens;
+        state.marketCapSol = trade.marketCapSol;
+        state.spotPrice = v_sol / v_tokens;
+        state.realSolReserves = real_sol;
+        state.graduationProgressPct = progress;
+        state.lastSignature = trade.signature.clone();
+        state.updatedAt = at;
+    }
+
+    pub fn mark_graduated(&mut self, mint: &str) {
+        if let Some(state) = self.states.get_mut(mint) {
+            state.graduated = true;
+            state.graduationProgressPct = state.graduationProgressPct.map(|_| 100.0);
+        }
+    }
+
+    // Latest state per mint, dropping mints idle for longer than the TTL as of the newest event
+    pub fn snapshot(&mut self) -> Vec<BondingCurveState> {
+        if let Some(latest) = self.latest_at {
+            let ttl = Duration::seconds(self.config.ttl_secs as i64);
+            self.states.retain(|_, state| latest - state.updatedAt < ttl);
+        }
+
+        let mut states: Vec<BondingCurveState> = self.states.values().cloned().collect();
+        states.sort_by(|a, b| a.mint.cmp(&b.mint));
+        states
+    }
+}
+
+// Replace the snapshot file in one step so readers never see half of it
+pub fn write_snapshot(path: &Path, states: &[BondingCurveState]) -> Result<(), Box<dyn Error>> {
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(states)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INITIAL_TOKENS: f64 = 1_073_000_000.0;
+    // Constant product of the default curve
+    const K: f64 = 30.0 * INITIAL_TOKENS;
+
+    // A trade leaving v_tokens on the curve, with the matching SOL reserves
+    fn trade(signature: &str, tx_type: &str, token_amount: f64, v_tokens: f64) -> Trade {
+        Trade {
+            signature: signature.to_string(),
+            mint: "mintA".to_string(),
+            traderPublicKey: "wallet".to_string(),
+            txType: tx_type.to_string(),
+            tokenAmount: token_amount,
+            solAmount: 0.1,
+            newTokenBalance: None,
+            bondingCurveKey: Some("curve".to_string()),
+            vTokensInBondingCurve: Some(v_tokens),
+            vSolInBondingCurve: Some(K / v_tokens),
+            marketCapSol: 30.0,
+            pool: "pump".to_string(),
+            spotPrice: None,
+            realSolReserves: None,
+            graduationProgressPct: None,
+            curveStatus: None,
+            meta: None,
+        }
+    }
+
+    fn apply(tracker: &mut BondingCurveTracker, mut trade: Trade) -> Trade {
+        tracker.apply_trade(&mut trade, Utc::now());
+        trade
+    }
+
+    fn state(tracker: &mut BondingCurveTracker) -> BondingCurveState {
+        tracker.snapshot().into_iter().next().expect("mint is tracked")
+    }
+
+    #[test]
+    fn buys_and_sells_follow_the_curve() {
+        let mut tracker = BondingCurveTracker::new(BondingCurveConfig::default());
+        let first = apply(&mut tracker, trade("s1", "buy", 1_000_000.0, 1_072_000_000.0));
+        let buy = apply(&mut tracker, trade("s2", "buy", 2_000_000.0, 1_070_000_000.0));
+        let sell = apply(&mut tracker, trade("s3", "sell", 500_000.0, 1_070_500_000.0));
+
+        assert_eq!(first.curveStatus, Some(CurveStatus::First));
+        assert_eq!(buy.curveStatus, Some(CurveStatus::InOrder));
+        assert_eq!(sell.curveStatus, Some(CurveStatus::InOrder));
+        assert!(sell.spotPrice.unwrap() < buy.spotPrice.unwrap());
+        assert!(buy.graduationProgressPct.unwrap() > first.graduationProgressPct.unwrap());
+
+        let state = state(&mut tracker);
+        assert_eq!(state.vTokensInBondingCurve, 1_070_500_000.0);
+        assert_eq!(state.lastSignature, "s3");
+        assert_eq!((state.trades, state.gaps, state.outOfOrder), (3, 0, 0));
+    }
+
+    #[test]
+    fn replayed_trade_is_out_of_order_and_not_applied() {
+        let mut tracker = BondingCurveTracker::new(BondingCurveConfig::default());
+        apply(&mut tracker, trade("s1", "buy", 1_000_000.0, 1_072_000_000.0));
+        apply(&mut tracker, trade("s2", "buy", 2_000_000.0, 1_070_000_000.0));
+        apply(&mut tracker, trade("s3", "sell", 500_000.0, 1_070_500_000.0));
+        let replayed = apply(&mut tracker, trade("s2", "buy", 2_000_000.0, 1_070_000_000.0));
+
+        assert_eq!(replayed.curveStatus, Some(CurveStatus::OutOfOrder));
+        let state = state(&mut tracker);
+        assert_eq!(state.vTokensInBondingCurve, 1_070_500_000.0);
+        assert_eq!(state.lastSignature, "s3");
+        assert_eq!(state.outOfOrder, 1);
+    }
+
+    #[test]
+    fn missed_trades_are_a_gap_and_applied() {
+        let mut tracker = BondingCurveTracker::new(BondingCurveConfig::default());
+        apply(&mut tracker, trade("s1", "buy", 1_000_000.0, 1_072_000_000.0));
+        let after_gap = apply(&mut tracker, trade("s5", "buy", 1_000_000.0, 1_060_000_000.0));
+
+        assert_eq!(after_gap.curveStatus, Some(CurveStatus::Gap));
+        let state = state(&mut tracker);
+        assert_eq!(state.vTokensInBondingCurve, 1_060_000_000.0);
+        assert_eq!(state.gaps, 1);
+    }
+
+    #[test]
+    fn reserves_off_the_curve_are_inconsistent() {
+        let mut tracker = BondingCurveTracker::new(BondingCurveConfig::default());
+        apply(&mut tracker, trade("s1", "buy", 1_000_000.0, 1_072_000_000.0));
+        let mut off_curve = trade("s2", "buy", 2_000_000.0, 1_070_000_000.0);
+        off_curve.vSolInBondingCurve = Some(60.0);
+        let off_curve = apply(&mut tracker, off_curve);
+
+        assert_eq!(off_curve.curveStatus, Some(CurveStatus::Inconsistent));
+        let state = state(&mut tracker);
+        assert_eq!(state.vTokensInBondingCurve, 1_072_000_000.0);
+        assert_eq!(state.inconsistent, 1);
+    }
+}
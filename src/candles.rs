@@ -0,0 +1,220 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::event::{Candle, Trade};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CandleConfig {
+    pub enabled: bool,
+    // Candle widths, in seconds
+    pub intervals_secs: Vec<u64>,
+}
+
+impl Default for CandleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            intervals_secs: vec![60, 300],
+        }
+    }
+}
+
+// Per-mint OHLCV candles built from bonding curve trades; a candle is emitted once
+// a later trade or the clock moves past its interval
+pub struct CandleBuilder {
+    intervals_secs: Vec<u64>,
+    open: HashMap<(String, u64), Candle>,
+}
+
+impl CandleBuilder {
+    pub fn new(config: &CandleConfig) -> Self {
+        Self {
+            intervals_secs: config.intervals_secs.iter().copied().filter(|secs| *secs > 0).collect(),
+            open: HashMap::new(),
+        }
+    }
+
+    // Fold a trade into its candles; returns candles the trade closed
+    pub fn on_trade(&mut self, trade: &Trade, at: DateTime<Utc>) -> Vec<Candle> {
+        // AMM trades carry no bonding curve reserves, so there is no price to chart
+        let (Some(v_sol), Some(v_tokens)) = (trade.vSolInBondingCurve, trade.vTokensInBondingCurve) else {
+            return Vec::new();
+        };
+        if v_tokens <= 0.0 {
+            return Vec::new();
+        }
+        let price = v_sol / v_tokens;
+        let is_buy = trade.txType == "buy";
+
+        let mut closed = Vec::new();
+        for &interval in &self.intervals_secs {
+            let open_time = bucket_start(at, interval);
+            let key = (trade.mint.clone(), interval);
+
+            // A trade stamped before the open candle's bucket still counts toward it
+            if let Some(candle) = self.open.get(&key)
+                && candle.openTime < open_time
+            {
+                closed.extend(self.open.remove(&key));
+            }
+
+            let candle = self.open.entry(key).or_insert_with(|| Candle {
+                mint: trade.mint.clone(),
+                intervalSecs: interval,
+                openTime: open_time,
+                closeTime: open_time + Duration::seconds(interval as i64),
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volumeSol: 0.0,
+                volumeTokens: 0.0,
+                buyVolumeSol: 0.0,
+                sellVolumeSol: 0.0,
+                tradeCount: 0,
+                buyCount: 0,
+                sellCount: 0,
+                firstTradeAt: at,
+                lastTradeAt: at,
+            });
+            candle.high = candle.high.max(price);
+            candle.low = candle.low.min(price);
+            // Trades can arrive out of order within the interval
+            if at >= candle.lastTradeAt {
+                candle.close = price;
+                candle.lastTradeAt = at;
+            }
+            if at < candle.firstTradeAt {
+                candle.open = price;
+                candle.firstTradeAt = at;
+            }
+            candle.volumeSol += trade.solAmount;
+            candle.volumeTokens += trade.tokenAmount;
+            candle.tradeCount += 1;
+            if is_buy {
+                candle.buyVolumeSol += trade.solAmount;
+                candle.buyCount += 1;
+            } else {
+                candle.sellVolumeSol += trade.solAmount;
+                candle.sellCount += 1;
+            }
+        }
+        closed
+    }
+
+    // Candles whose interval has ended, for mints that stopped trading
+    pub fn close_expired(&mut self, now: DateTime<Utc>) -> Vec<Candle> {
+        let expired: Vec<(String, u64)> = self
+            .open
+            .iter()
+            .filter(|(_, candle)| candle.closeTime <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        expired.into_iter().filter_map(|key| self.open.remove(&key)).collect()
+    }
+
+    // Every open candle, complete or not; used at shutdown
+    pub fn drain(&mut self) -> Vec<Candle> {
+        self.open.drain().map(|(_, candle)| candle).collect()
+    }
+}
+
+fn bucket_start(at: DateTime<Utc>, interval_secs: u64) -> DateTime<Utc> {
+    let interval = interval_secs as i64;
+    let secs = at.timestamp().div_euclid(interval) * interval;
+    Utc.timestamp_opt(secs, 0).single().unwrap_or(at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> CandleBuilder {
+        CandleBuilder::new(&CandleConfig {
+            enabled: true,
+            intervals_secs: vec![60],
+        })
+    }
+
+    // A trade at the given SOL-per-token price
+    fn trade(tx_type: &str, price: f64, sol_amount: f64) -> Trade {
+        Trade {
+            signature: "sig".to_string(),
+            mint: "mintA".to_string(),
+            traderPublicKey: "wallet".to_string(),
+            txType: tx_type.to_string(),
+            tokenAmount: sol_amount / price,
+            solAmount: sol_amount,
+            newTokenBalance: None,
+            bondingCurveKey: Some("curve".to_string()),
+            vTokensInBondingCurve: Some(1000.0),
+            vSolInBondingCurve: Some(price * 1000.0),
+            marketCapSol: 30.0,
+            pool: "pump".to_string(),
+            spotPrice: None,
+            realSolReserves: None,
+            graduationProgressPct: None,
+            curveStatus: None,
+            meta: None,
+        }
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_773_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn trades_in_one_interval_build_one_candle() {
+        let mut candles = builder();
+        assert!(candles.on_trade(&trade("buy", 1.0, 2.0), at(1)).is_empty());
+        assert!(candles.on_trade(&trade("buy", 3.0, 1.0), at(10)).is_empty());
+        assert!(candles.on_trade(&trade("sell", 2.0, 0.5), at(20)).is_empty());
+
+        let candle = candles.drain().pop().unwrap();
+        assert_eq!((candle.open, candle.high, candle.low, candle.close), (1.0, 3.0, 1.0, 2.0));
+        assert_eq!(candle.volumeSol, 3.5);
+        assert_eq!((candle.buyVolumeSol, candle.sellVolumeSol), (3.0, 0.5));
+        assert_eq!((candle.tradeCount, candle.buyCount, candle.sellCount), (3, 2, 1));
+        assert_eq!((candle.firstTradeAt, candle.lastTradeAt), (at(1), at(20)));
+        assert_eq!(candle.closeTime - candle.openTime, Duration::seconds(60));
+    }
+
+    #[test]
+    fn trade_in_the_next_interval_closes_the_candle() {
+        let mut candles = builder();
+        candles.on_trade(&trade("buy", 1.0, 1.0), at(10));
+        let closed = candles.on_trade(&trade("buy", 2.0, 1.0), at(70));
+
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].close, 1.0);
+        assert_eq!(closed[0].tradeCount, 1);
+        let open = candles.drain().pop().unwrap();
+        assert_eq!(open.open, 2.0);
+        assert!(open.openTime >= closed[0].closeTime);
+    }
+
+    #[test]
+    fn late_trade_within_the_interval_becomes_the_open() {
+        let mut candles = builder();
+        candles.on_trade(&trade("buy", 2.0, 1.0), at(30));
+        candles.on_trade(&trade("buy", 1.0, 1.0), at(10));
+
+        let candle = candles.drain().pop().unwrap();
+        assert_eq!((candle.open, candle.close), (1.0, 2.0));
+        assert_eq!((candle.firstTradeAt, candle.lastTradeAt), (at(10), at(30)));
+        assert_eq!(candle.tradeCount, 2);
+    }
+
+    #[test]
+    fn close_expired_waits_for_the_interval_to_end() {
+        let mut candles = builder();
+        candles.on_trade(&trade("buy", 1.0, 1.0), at(10));
+        let close_time = candles.open.values().next().unwrap().closeTime;
+
+        assert!(candles.close_expired(close_time - Duration::seconds(1)).is_empty());
+        assert_eq!(candles.close_expired(close_time).len(), 1);
+        assert!(candles.drain().is_empty());
+    }
+}
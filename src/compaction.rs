@@ -0,0 +1,474 @@
+use arrow::array::{Array, RecordBatch};
+use arrow::compute::{concat, interleave_record_batch, lexsort_to_indices, SortColumn};
+use arrow::datatypes::SchemaRef;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::arrow::{dataset_schemas, DATASET_KEY, SCHEMA_VERSION_KEY};
+use crate::parquet_storage::{create_temp, sync_dir, temp_name, write_file, ParquetConfig};
+
+const LOCK_FILE: &str = ".compact.lock";
+const MANIFEST_SUFFIX: &str = ".compact_manifest";
+
+// Written before a compacted file is published so an interrupted run can be finished
+// (output published: drop the inputs) or rolled back (output missing: keep them)
+#[derive(Debug, Serialize, Deserialize)]
+struct CompactionManifest {
+    output: String,
+    temp: String,
+    inputs: Vec<String>,
+}
+
+// A small file waiting to be merged, with what decides which files may share an output
+struct SmallFile {
+    path: PathBuf,
+    bytes: u64,
+    rows: usize,
+    schema: SchemaRef,
+    schema_version: Option<String>,
+    // From the file's metadata, or the dataset whose current schema it matches
+    dataset: Option<String>,
+}
+
+#[derive(Default)]
+struct CompactionStats {
+    partitions: usize,
+    inputs: usize,
+    outputs: usize,
+    bytes_in: u64,
+    bytes_out: u64,
+}
+
+// Only one compaction at a time. The lock is an flock on the lock file, so it goes with the
+// process however it ends; the file itself stays, since deleting it would let a second run
+// lock a fresh file while a third still holds the old one
+struct CompactionLock {
+    _file: File,
+}
+
+impl CompactionLock {
+    fn acquire(base: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = base.join(LOCK_FILE);
+        let file = OpenOptions::new().write(true).create(true).truncate(false).open(&path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(format!("Another compaction holds {}", path.display()).into()),
+            Err(TryLockError::Error(e)) => Err(format!("Failed to lock {}: {}", path.display(), e).into()),
+        }
+    }
+}
+
+// Merge small Parquet files within each partition into files of about target_bytes, and
+// at most max_rows rows, since a whole output is held in memory while it is sorted.
+// Ingest only publishes complete files by rename, so this can run alongside it.
+pub fn compact_storage(
+    base_path: &str,
+    config: &ParquetConfig,
+    target_bytes: u64,
+    max_rows: usize,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    config.validate()?;
+    let base = Path::new(base_path);
+    let _lock = CompactionLock::acquire(base)?;
+
+    let mut partitions = Vec::new();
+    find_partitions(base, &mut partitions)?;
+
+    let mut stats = CompactionStats::default();
+    for partition in partitions {
+        recover_manifests(&partition)?;
+        compact_partition(&partition, config, target_bytes, max_rows, dry_run, &mut stats)?;
+    }
+
+    let verb = if dry_run { "Would compact" } else { "✅ Compacted" };
+    println!(
+        "{} {} files into {} across {} partitions ({:.1} MB -> {:.1} MB)",
+        verb,
+        stats.inputs,
+        stats.outputs,
+        stats.partitions,
+        stats.bytes_in as f64 / 1e6,
+        stats.bytes_out as f64 / 1e6,
+    );
+    Ok(())
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') || name.starts_with('_'))
+}
+
+fn is_data_file(path: &Path) -> bool {
+    !is_hidden(path) && path.extension().is_some_and(|extension| extension == "parquet")
+}
+
+// Every directory holding Parquet files (or leftover manifests), skipping hidden ones like _quarantine
+fn find_partitions(dir: &Path, found: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    let mut has_files = false;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if !is_hidden(&path) {
+                find_partitions(&path, found)?;
+            }
+        } else if is_data_file(&path) || path.to_string_lossy().ends_with(MANIFEST_SUFFIX) {
+            has_files = true;
+        }
+    }
+    if has_files {
+        found.push(dir.to_path_buf());
+    }
+    Ok(())
+}
+
+// Finish or roll back runs that stopped between writing a manifest and removing it
+fn recover_manifests(partition: &Path) -> Result<(), Box<dyn Error>> {
+    for entry in fs::read_dir(partition)? {
+        let path = entry?.path();
+        if !path.to_string_lossy().ends_with(MANIFEST_SUFFIX) {
+            continue;
+        }
+        let manifest: CompactionManifest = serde_json::from_slice(&fs::read(&path)?)?;
+        if partition.join(&manifest.output).exists() {
+            for input in &manifest.inputs {
+                let input = partition.join(input);
+                if input.exists() {
+                    fs::remove_file(input)?;
+                }
+            }
+            info!("Finished interrupted compaction into {}", manifest.output);
+        } else {
+            let temp = partition.join(&manifest.temp);
+            if temp.exists() {
+                fs::remove_file(temp)?;
+            }
+            info!("Rolled back interrupted compaction into {}", manifest.output);
+        }
+        fs::remove_file(&path)?;
+        sync_dir(partition)?;
+    }
+    Ok(())
+}
+
+fn read_small_file(path: PathBuf, bytes: u64) -> Result<SmallFile, Box<dyn Error>> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&path)?)?;
+    let metadata = builder.metadata().file_metadata().key_value_metadata();
+    let value = |key: &str| {
+        metadata
+            .and_then(|pairs| pairs.iter().find(|pair| pair.key == key))
+            .and_then(|pair| pair.value.clone())
+    };
+    let schema = builder.schema().clone();
+    let rows = builder.metadata().file_metadata().num_rows() as usize;
+    let dataset = value(DATASET_KEY).or_else(|| {
+        dataset_schemas()
+            .into_iter()
+            .find(|(_, dataset_schema)| dataset_schema.fields() == schema.fields())
+            .map(|(name, _)| name.to_string())
+    });
+    Ok(SmallFile {
+        path,
+        bytes,
+        rows,
+        schema,
+        schema_version: value(SCHEMA_VERSION_KEY),
+        dataset,
+    })
+}
+
+fn compact_partition(
+    partition: &Path,
+    config: &ParquetConfig,
+    target_bytes: u64,
+    max_rows: usize,
+    dry_run: bool,
+    stats: &mut CompactionStats,
+) -> Result<(), Box<dyn Error>> {
+    let mut small = Vec::new();
+    for entry in fs::read_dir(partition)? {
+        let entry = entry?;
+        let path = entry.path();
+        let bytes = entry.metadata()?.len();
+        if is_data_file(&path) && bytes < target_bytes {
+            // An unreadable file is left for someone to look at rather than ending the run
+            match read_small_file(path.clone(), bytes) {
+                Ok(file) => small.push(file),
+                Err(e) => warn!("Skipping {}: {}", path.display(), e),
+            }
+        }
+    }
+    // Oldest first, so outputs hold roughly contiguous stretches of time
+    small.sort_by(|a, b| a.path.cmp(&b.path));
+
+    // Files only merge with files of the same dataset, schema and schema version
+    let mut groups: Vec<Vec<SmallFile>> = Vec::new();
+    for file in small {
+        if file.dataset.is_none() {
+            warn!("Skipping {}: not a known dataset schema", file.path.display());
+            continue;
+        }
+        match groups.iter_mut().find(|group| {
+            group[0].dataset == file.dataset
+                && group[0].schema_version == file.schema_version
+                && group[0].schema.fields() == file.schema.fields()
+        }) {
+            Some(group) => group.push(file),
+            None => groups.push(vec![file]),
+        }
+    }
+
+    let mut compacted = false;
+    for group in groups {
+        for bin in bin_files(group, target_bytes, max_rows) {
+            if bin.len() < 2 {
+                continue;
+            }
+            let bytes_in: u64 = bin.iter().map(|file| file.bytes).sum();
+            stats.inputs += bin.len();
+            stats.outputs += 1;
+            stats.bytes_in += bytes_in;
+            if dry_run {
+                stats.bytes_out += bytes_in;
+                continue;
+            }
+            stats.bytes_out += merge_files(partition, config, &bin)?;
+            compacted = true;
+        }
+    }
+    if compacted || dry_run {
+        stats.partitions += 1;
+    }
+    Ok(())
+}
+
+// Consecutive files up to about target_bytes and max_rows each
+fn bin_files(files: Vec<SmallFile>, target_bytes: u64, max_rows: usize) -> Vec<Vec<SmallFile>> {
+    let mut bins = Vec::new();
+    let mut bin = Vec::new();
+    let mut bin_bytes = 0;
+    let mut bin_rows = 0;
+    for file in files {
+        if !bin.is_empty() && (bin_bytes + file.bytes > target_bytes || bin_rows + file.rows > max_rows) {
+            bins.push(std::mem::take(&mut bin));
+            bin_bytes = 0;
+            bin_rows = 0;
+        }
+        bin_bytes += file.bytes;
+        bin_rows += file.rows;
+        bin.push(file);
+    }
+    if !bin.is_empty() {
+        bins.push(bin);
+    }
+    bins
+}
+
+// Rows written per output batch, so the sorted copy is built a slice at a time
+const OUTPUT_BATCH_ROWS: usize = 64 * 1024;
+
+// Order of the rows across batches: by mint, then by event time when the dataset has one.
+// Only the sort columns are concatenated; the rows stay where they are
+fn sorted_positions(batches: &[RecordBatch]) -> Result<Vec<(usize, usize)>, Box<dyn Error>> {
+    let mut starts = Vec::with_capacity(batches.len());
+    let mut total = 0;
+    for batch in batches {
+        starts.push(total);
+        total += batch.num_rows();
+    }
+    let position = |row: usize| {
+        let batch = starts.partition_point(|&start| start <= row) - 1;
+        (batch, row - starts[batch])
+    };
+
+    let mut columns = Vec::new();
+    for name in ["mint", "received_at", "open_time"] {
+        let Some(index) = batches.first().and_then(|batch| batch.schema().index_of(name).ok()) else {
+            continue;
+        };
+        let arrays: Vec<&dyn Array> = batches.iter().map(|batch| batch.column(index).as_ref()).collect();
+        columns.push(SortColumn {
+            values: concat(&arrays)?,
+            options: None,
+        });
+    }
+    if columns.is_empty() {
+        return Ok((0..total).map(position).collect());
+    }
+    let indices = lexsort_to_indices(&columns, None)?;
+    Ok(indices.values().iter().map(|&row| position(row as usize)).collect())
+}
+
+// Returns the size of the merged file
+fn merge_files(partition: &Path, config: &ParquetConfig, bin: &[SmallFile]) -> Result<u64, Box<dyn Error>> {
+    let schema = bin[0].schema.clone();
+    let mut batches = Vec::new();
+    for file in bin {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&file.path)?)?.build()?;
+        for batch in reader {
+            batches.push(batch?);
+        }
+    }
+    let positions = sorted_positions(&batches)?;
+    let batch_refs: Vec<&RecordBatch> = batches.iter().collect();
+    let sorted = positions
+        .chunks(OUTPUT_BATCH_ROWS)
+        .map(|chunk| Ok(interleave_record_batch(&batch_refs, chunk)?));
+
+    let id = uuid::Uuid::new_v4().simple();
+    let output = format!("compacted_{}_{}.parquet", chrono::Utc::now().format("%Y%m%d%H%M%S"), id);
+    let temp = temp_name(&output);
+    let temp_path = partition.join(&temp);
+    // Rewritten with the dataset's current writer settings
+    let dataset = bin[0].dataset.as_deref().expect("grouped files have a dataset");
+    let props = config
+        .writer_settings(dataset)
+        .properties(dataset, &schema, bin[0].schema_version.clone(), positions.len())?;
+    // Locked until the rename so a starting ingest doesn't quarantine it mid-swap
+    let temp_file = create_temp(&temp_path)?;
+    let written = temp_file
+        .try_clone()
+        .map_err(Into::into)
+        .and_then(|file| write_file(file, schema.clone(), sorted, props));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    // Record the swap before making it, so a crash part-way is recovered on the next run
+    let manifest = CompactionManifest {
+        output: output.clone(),
+        temp,
+        inputs: bin
+            .iter()
+            .filter_map(|file| file.path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect(),
+    };
+    let manifest_path = partition.join(format!(".{}{}", id, MANIFEST_SUFFIX));
+    let manifest_file = File::create(&manifest_path)?;
+    serde_json::to_writer(&manifest_file, &manifest)?;
+    manifest_file.sync_all()?;
+    sync_dir(partition)?;
+
+    let output_path = partition.join(&output);
+    fs::rename(&temp_path, &output_path)?;
+    drop(temp_file);
+    sync_dir(partition)?;
+    for file in bin {
+        fs::remove_file(&file.path)?;
+    }
+    fs::remove_file(&manifest_path)?;
+    sync_dir(partition)?;
+
+    let bytes = fs::metadata(&output_path)?.len();
+    info!("Compacted {} files into {}", bin.len(), output_path.display());
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::Schema;
+    use std::sync::Arc;
+
+    fn small_file(name: &str, bytes: u64, rows: usize) -> SmallFile {
+        SmallFile {
+            path: PathBuf::from(name),
+            bytes,
+            rows,
+            schema: Arc::new(Schema::empty()),
+            schema_version: None,
+            dataset: Some("trade".to_string()),
+        }
+    }
+
+    fn bin_names(bins: &[Vec<SmallFile>]) -> Vec<Vec<String>> {
+        bins.iter()
+            .map(|bin| bin.iter().map(|file| file.path.to_string_lossy().into_owned()).collect())
+            .collect()
+    }
+
+    fn partition_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pumptrace-compaction-{}", uuid::Uuid::new_v4().simple()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_manifest(partition: &Path, manifest: &CompactionManifest) {
+        let path = partition.join(format!(".run{}", MANIFEST_SUFFIX));
+        fs::write(path, serde_json::to_vec(manifest).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn bins_split_on_bytes() {
+        let files = vec![small_file("a", 40, 1), small_file("b", 40, 1), small_file("c", 40, 1)];
+        let bins = bin_files(files, 100, usize::MAX);
+        assert_eq!(bin_names(&bins), vec![vec!["a", "b"], vec!["c"]]);
+    }
+
+    #[test]
+    fn bins_split_on_rows() {
+        let files = vec![small_file("a", 1, 600), small_file("b", 1, 600), small_file("c", 1, 300)];
+        let bins = bin_files(files, 100, 1000);
+        assert_eq!(bin_names(&bins), vec![vec!["a"], vec!["b", "c"]]);
+    }
+
+    #[test]
+    fn oversized_file_gets_its_own_bin() {
+        let files = vec![small_file("a", 10, 1), small_file("b", 500, 1), small_file("c", 10, 1)];
+        let bins = bin_files(files, 100, usize::MAX);
+        assert_eq!(bin_names(&bins), vec![vec!["a"], vec!["b"], vec!["c"]]);
+    }
+
+    #[test]
+    fn recovery_finishes_run_whose_output_was_published() {
+        let partition = partition_dir();
+        for name in ["a.parquet", "b.parquet", "out.parquet"] {
+            fs::write(partition.join(name), b"data").unwrap();
+        }
+        write_manifest(&partition, &CompactionManifest {
+            output: "out.parquet".to_string(),
+            temp: ".out.parquet.1.tmp".to_string(),
+            inputs: vec!["a.parquet".to_string(), "b.parquet".to_string()],
+        });
+
+        recover_manifests(&partition).unwrap();
+
+        let mut left: Vec<String> = fs::read_dir(&partition)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["out.parquet"]);
+        fs::remove_dir_all(&partition).unwrap();
+    }
+
+    #[test]
+    fn recovery_rolls_back_run_whose_output_is_missing() {
+        let partition = partition_dir();
+        for name in ["a.parquet", "b.parquet", ".out.parquet.1.tmp"] {
+            fs::write(partition.join(name), b"data").unwrap();
+        }
+        write_manifest(&partition, &CompactionManifest {
+            output: "out.parquet".to_string(),
+            temp: ".out.parquet.1.tmp".to_string(),
+            inputs: vec!["a.parquet".to_string(), "b.parquet".to_string()],
+        });
+
+        recover_manifests(&partition).unwrap();
+
+        let mut left: Vec<String> = fs::read_dir(&partition)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["a.parquet", "b.parquet"]);
+        fs::remove_dir_all(&partition).unwrap();
+    }
+}
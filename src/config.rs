@@ -0,0 +1,235 @@
+use clap::{Args, Parser, Subcommand};
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::auto_follow::AutoFollowConfig;
+use crate::bonding_curve::BondingCurveConfig;
+use crate::candles::CandleConfig;
+use crate::dead_letter::DeadLetterConfig;
+use crate::journal::JournalConfig;
+use crate::parquet_storage::ParquetConfig;
+use crate::postgres_db::PostgresConfig;
+use crate::sink::{SinkConfig, SinkKind};
+use crate::source::{SourceConfig, SourceKind};
+use crate::subscriptions::SubscriptionConfig;
+
+#[derive(Debug, Parser)]
+#[command(name = "pumptrace", about = "Capture PumpPortal events into Parquet and Postgres")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path to a TOML config file
+    #[arg(long, short, global = true)]
+    pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub subscriptions: SubscriptionArgs,
+
+    /// Where raw events come from
+    #[arg(long, value_enum, global = true)]
+    pub source: Option<SourceKind>,
+
+    /// Outputs to write to, replacing the configured set
+    #[arg(long, value_enum, value_delimiter = ',', global = true)]
+    pub sinks: Option<Vec<SinkKind>>,
+
+    #[command(flatten)]
+    pub postgres: PostgresArgs,
+
+    /// Input file for the file and mock sources
+    #[arg(long, value_name = "PATH", global = true)]
+    pub source_path: Option<PathBuf>,
+
+    /// Subscribe to trades for every newly launched token
+    #[arg(long, global = true)]
+    pub auto_follow: bool,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Stream events from the configured source (default)
+    Ingest,
+    /// Run dead-lettered messages through the pipeline again
+    ReprocessDeadLetters,
+    /// Feed journaled websocket frames back through the pipeline
+    Replay {
+        /// Journal files to replay (default: every file under <storage>/journal)
+        files: Vec<PathBuf>,
+
+        /// Playback speed relative to the original receive times
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+
+        /// Replay as fast as possible, ignoring original timing
+        #[arg(long, conflicts_with = "speed")]
+        max_speed: bool,
+    },
+    /// Merge small Parquet files within each partition into larger sorted files
+    Compact {
+        /// Size to aim for per compacted file, in MB; larger files are left alone
+        #[arg(long, default_value_t = 128)]
+        target_file_mb: u64,
+
+        /// Most rows per compacted file; a file's rows are held in memory while it is sorted
+        #[arg(long, default_value_t = 2_000_000)]
+        max_file_rows: usize,
+
+        /// Report what would be merged without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Manage the Postgres schema
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateCommand,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum MigrateCommand {
+    /// Apply every pending migration
+    Up,
+    /// List migrations and whether each has been applied
+    Status,
+}
+
+#[derive(Debug, Args)]
+pub struct SubscriptionArgs {
+    /// Do not subscribe to new token launches
+    #[arg(long)]
+    pub no_new_tokens: bool,
+
+    /// Mint to subscribe trades for (repeatable)
+    #[arg(long = "token-trade", value_name = "MINT")]
+    pub token_trades: Vec<String>,
+
+    /// Wallet to subscribe trades for (repeatable)
+    #[arg(long = "account-trade", value_name = "WALLET")]
+    pub account_trades: Vec<String>,
+
+    /// Subscribe to migration events
+    #[arg(long)]
+    pub migrations: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct PostgresArgs {
+    /// Postgres connection URL (also read from DATABASE_URL)
+    #[arg(long, value_name = "URL", global = true)]
+    pub database_url: Option<String>,
+
+    /// Maximum Postgres pool connections
+    #[arg(long, value_name = "N", global = true)]
+    pub pg_max_connections: Option<u32>,
+
+    /// Postgres sslmode, e.g. disable, require, verify-full
+    #[arg(long, value_name = "MODE", global = true)]
+    pub pg_ssl_mode: Option<String>,
+
+    /// Postgres schema for all tables
+    #[arg(long, value_name = "SCHEMA", global = true)]
+    pub pg_schema: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub ws_url: String,
+    pub storage_path: String,
+    pub buffer_size: usize,
+    // Upper bound on how long a buffered event waits before sinks are flushed
+    pub flush_interval_secs: u64,
+    pub subscriptions: SubscriptionConfig,
+    pub auto_follow: AutoFollowConfig,
+    pub dead_letters: DeadLetterConfig,
+    pub journal: JournalConfig,
+    pub source: SourceConfig,
+    pub sinks: SinkConfig,
+    pub postgres: PostgresConfig,
+    pub candles: CandleConfig,
+    pub bonding_curve: BondingCurveConfig,
+    pub parquet: ParquetConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            ws_url: "wss://pumpportal.fun/api/data".to_string(),
+            storage_path: "./pump_data".to_string(),
+            buffer_size: 10_000,
+            flush_interval_secs: 60,
+            subscriptions: SubscriptionConfig::default(),
+            auto_follow: AutoFollowConfig::default(),
+            dead_letters: DeadLetterConfig::default(),
+            journal: JournalConfig::default(),
+            source: SourceConfig::default(),
+            sinks: SinkConfig::default(),
+            postgres: PostgresConfig::default(),
+            candles: CandleConfig::default(),
+            bonding_curve: BondingCurveConfig::default(),
+            parquet: ParquetConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    // Config file first, then environment, then CLI flags on top
+    pub fn load(cli: &Cli) -> Result<Self, Box<dyn Error>> {
+        let mut config = match &cli.config {
+            Some(path) => {
+                let contents = fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+                toml::from_str(&contents)
+                    .map_err(|e| format!("Failed to parse config {}: {}", path.display(), e))?
+            }
+            None => Config::default(),
+        };
+
+        config.postgres.apply_env()?;
+        let pg = &cli.postgres;
+        if let Some(url) = &pg.database_url {
+            config.postgres.url = url.clone();
+        }
+        if let Some(max) = pg.pg_max_connections {
+            config.postgres.max_connections = max;
+        }
+        if let Some(ssl_mode) = &pg.pg_ssl_mode {
+            config.postgres.ssl_mode = Some(ssl_mode.clone());
+        }
+        if let Some(schema) = &pg.pg_schema {
+            config.postgres.schema = schema.clone();
+        }
+
+        let subs = &cli.subscriptions;
+        if subs.no_new_tokens {
+            config.subscriptions.new_tokens = false;
+        }
+        config.subscriptions.token_trades.extend(subs.token_trades.iter().cloned());
+        config.subscriptions.account_trades.extend(subs.account_trades.iter().cloned());
+        if subs.migrations {
+            config.subscriptions.migrations = true;
+        }
+        if let Some(kind) = cli.source {
+            config.source.kind = kind;
+        }
+        if let Some(path) = &cli.source_path {
+            config.source.path = Some(path.clone());
+        }
+        if let Some(sinks) = &cli.sinks {
+            config.sinks.enabled = sinks.clone();
+        }
+        if cli.auto_follow {
+            config.auto_follow.enabled = true;
+        }
+
+        // The flush timer cannot tick at a zero period
+        if config.flush_interval_secs == 0 {
+            return Err("flush_interval_secs must be at least 1".into());
+        }
+
+        Ok(config)
+    }
+}
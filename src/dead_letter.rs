@@ -0,0 +1,164 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, create_dir_all, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::event::{Candle, EventSequencer, PumpEvent};
+use crate::process_data::PumpPipeline;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DeadLetterConfig {
+    pub enabled: bool,
+    // Also insert dead letters into the dead_letters table
+    pub postgres: bool,
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            postgres: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub received_at: DateTime<Utc>,
+    pub reason: String,
+    pub raw: String,
+}
+
+impl DeadLetter {
+    // received_at is when the message or row arrived, not when it failed
+    pub fn new(raw: &str, reason: impl Into<String>, received_at: DateTime<Utc>) -> Self {
+        Self {
+            received_at,
+            reason: reason.into(),
+            raw: raw.to_string(),
+        }
+    }
+}
+
+// Daily JSONL files under <storage>/dead_letters
+pub struct DeadLetterStore {
+    dir: PathBuf,
+}
+
+impl DeadLetterStore {
+    pub fn new(base_path: &str) -> Result<Self, Box<dyn Error>> {
+        let dir = Path::new(base_path).join("dead_letters");
+        create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    pub fn record(&self, letter: &DeadLetter) -> Result<(), Box<dyn Error>> {
+        let path = self.dir.join(format!("{}.jsonl", letter.received_at.format("%Y-%m-%d")));
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        // One write per line so appends from the pipeline and the Postgres writer don't interleave
+        let mut line = serde_json::to_string(letter)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut files: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == "jsonl" || ext == "reprocessing")
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    pub fn read_file(path: &Path) -> Result<Vec<DeadLetter>, Box<dyn Error>> {
+        let reader = BufReader::new(fs::File::open(path)?);
+        let mut letters = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(letter) => letters.push(letter),
+                Err(e) => warn!("Skipping corrupt dead letter in {}: {:?}", path.display(), e),
+            }
+        }
+        Ok(letters)
+    }
+}
+
+// Feed dead letters back through the pipeline; anything still unparseable is re-recorded
+pub async fn reprocess_dead_letters(config: Config) -> Result<(), Box<dyn Error>> {
+    let store = DeadLetterStore::new(&config.storage_path)?;
+    let postgres = PumpPipeline::connect_postgres(&config).await?;
+    let mut pipeline = PumpPipeline::new(&config, postgres)?;
+    let (mut recovered, mut remaining) = (0usize, 0usize);
+    let mut sequencer = EventSequencer::new();
+
+    for path in store.files()? {
+        // Move the file aside first so live ingestion keeps appending to a fresh one;
+        // a leftover .reprocessing file means an earlier run was interrupted
+        let claimed = if path.extension().is_some_and(|ext| ext == "reprocessing") {
+            path
+        } else {
+            let claimed = path.with_extension(format!("{}.reprocessing", uuid::Uuid::new_v4().simple()));
+            fs::rename(&path, &claimed)?;
+            claimed
+        };
+
+        for letter in DeadLetterStore::read_file(&claimed)? {
+            let event = serde_json::from_str::<serde_json::Value>(&letter.raw)
+                .map_err(|e| e.to_string())
+                .and_then(|raw| PumpEvent::from_value(raw).map_err(|e| e.to_string()));
+
+            match event {
+                // Candles a sink rejected carry no txType; they are derived, so they are written as
+                // they stand rather than rebuilt
+                Ok(PumpEvent::Unknown(unknown)) if unknown.txType.is_none() => {
+                    match serde_json::from_value::<Candle>(unknown.raw) {
+                        Ok(candle) => {
+                            pipeline.write_candle(candle).await;
+                            recovered += 1;
+                        }
+                        Err(_) => {
+                            store.record(&letter)?;
+                            remaining += 1;
+                        }
+                    }
+                }
+                Ok(PumpEvent::Unknown(_)) => {
+                    store.record(&letter)?;
+                    remaining += 1;
+                }
+                Ok(mut event) => {
+                    // Rows a sink rejected keep the receipt metadata they were serialized with
+                    if event.meta().is_none() {
+                        event.set_meta(sequencer.next(letter.received_at));
+                    }
+                    pipeline.process_data(event).await?;
+                    recovered += 1;
+                }
+                Err(reason) => {
+                    store.record(&DeadLetter { reason, ..letter })?;
+                    remaining += 1;
+                }
+            }
+        }
+
+        fs::remove_file(&claimed)?;
+    }
+
+    pipeline.flush_all().await?;
+    info!("Reprocessed dead letters: {} recovered, {} still failing", recovered, remaining);
+    eprintln!("✅ Reprocessed dead letters: {} recovered, {} still failing", recovered, remaining);
+    Ok(())
+}
@@ -0,0 +1,288 @@
+#![allow(non_snake_case)]
+
+use chrono::{DateTime, Utc};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::arrow::{SOL_PRECISION, SOL_SCALE, TOKEN_PRECISION, TOKEN_SCALE};
+use crate::bonding_curve::CurveStatus;
+
+// Field names follow the PumpPortal payloads so serde can map them directly
+
+// Stamped when a frame is received; orders and deduplicates events and measures latency
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct EventMeta {
+    pub receivedAt: DateTime<Utc>,
+    // Increases by one per received frame within a session
+    pub sequence: u64,
+    pub sessionId: Uuid,
+}
+
+// Hands out receipt metadata for one ingest, replay or reprocess run
+pub struct EventSequencer {
+    session_id: Uuid,
+    next_sequence: u64,
+}
+
+impl EventSequencer {
+    pub fn new() -> Self {
+        Self {
+            session_id: Uuid::new_v4(),
+            next_sequence: 0,
+        }
+    }
+
+    pub fn session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    pub fn next(&mut self, received_at: DateTime<Utc>) -> EventMeta {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        EventMeta {
+            receivedAt: received_at,
+            sequence,
+            sessionId: self.session_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenLaunch {
+    pub signature: String,
+    pub traderPublicKey: String,
+    pub txType: String,
+    pub mint: String,
+    // Only sent for some pools
+    pub solInPool: Option<f64>,
+    pub tokensInPool: Option<f64>,
+    pub initialBuy: f64,
+    pub solAmount: f64,
+    pub newTokenBalance: Option<f64>,
+    pub marketCapSol: f64,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub pool: String,
+    #[serde(flatten)]
+    pub meta: Option<EventMeta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub signature: String,
+    pub mint: String,
+    pub traderPublicKey: String,
+    pub txType: String,
+    pub tokenAmount: f64,
+    pub solAmount: f64,
+    pub newTokenBalance: Option<f64>,
+    // Absent once a token trades on an AMM pool instead of its bonding curve
+    pub bondingCurveKey: Option<String>,
+    pub vTokensInBondingCurve: Option<f64>,
+    pub vSolInBondingCurve: Option<f64>,
+    pub marketCapSol: f64,
+    pub pool: String,
+    // Derived by the bonding curve tracker, not sent by PumpPortal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spotPrice: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub realSolReserves: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graduationProgressPct: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curveStatus: Option<CurveStatus>,
+    #[serde(flatten)]
+    pub meta: Option<EventMeta>,
+}
+
+// A trade made by one of the configured watched wallets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletTrade {
+    pub watchedWallet: String,
+    #[serde(flatten)]
+    pub trade: Trade,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Migration {
+    pub signature: String,
+    pub mint: String,
+    pub txType: String,
+    pub pool: Option<String>,
+    #[serde(flatten)]
+    pub meta: Option<EventMeta>,
+}
+
+// Per-mint OHLCV candle built by the pipeline from trades; prices are SOL per token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candle {
+    pub mint: String,
+    pub intervalSecs: u64,
+    pub openTime: DateTime<Utc>,
+    pub closeTime: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volumeSol: f64,
+    pub volumeTokens: f64,
+    pub buyVolumeSol: f64,
+    pub sellVolumeSol: f64,
+    pub tradeCount: u64,
+    pub buyCount: u64,
+    pub sellCount: u64,
+    // Receive times of the first and last trade folded in
+    pub firstTradeAt: DateTime<Utc>,
+    pub lastTradeAt: DateTime<Utc>,
+}
+
+// Anything we don't model yet, kept verbatim
+#[derive(Debug, Clone, Serialize)]
+pub struct UnknownEvent {
+    pub txType: Option<String>,
+    pub raw: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+pub enum PumpEvent {
+    TokenLaunch(TokenLaunch),
+    Trade(Trade),
+    WalletTrade(WalletTrade),
+    Migration(Migration),
+    Candle(Candle),
+    Unknown(UnknownEvent),
+}
+
+impl PumpEvent {
+    // Dispatch on txType, then deserialize and validate the matching payload
+    pub fn from_value(raw: Value) -> Result<Self, serde_json::Error> {
+        let tx_type = match raw.get("txType") {
+            Some(Value::String(tx_type)) => Some(tx_type.clone()),
+            Some(other) => {
+                return Err(de::Error::custom(format!("txType is not a string: {}", other)));
+            }
+            None => None,
+        };
+
+        let event = match tx_type.as_deref() {
+            Some("create") => PumpEvent::TokenLaunch(parse_payload(&raw, "create")?),
+            Some("buy") | Some("sell") => PumpEvent::Trade(parse_payload(&raw, "trade")?),
+            Some("migrate") => PumpEvent::Migration(parse_payload(&raw, "migrate")?),
+            _ => return Ok(PumpEvent::Unknown(UnknownEvent { txType: tx_type, raw })),
+        };
+
+        event.validate()?;
+        Ok(event)
+    }
+
+    // Attach receipt metadata; derived events (wallet trades, candles) get theirs from the source trade
+    pub fn set_meta(&mut self, meta: EventMeta) {
+        match self {
+            PumpEvent::TokenLaunch(launch) => launch.meta = Some(meta),
+            PumpEvent::Trade(trade) => trade.meta = Some(meta),
+            PumpEvent::WalletTrade(wallet_trade) => wallet_trade.trade.meta = Some(meta),
+            PumpEvent::Migration(migration) => migration.meta = Some(meta),
+            PumpEvent::Candle(_) | PumpEvent::Unknown(_) => {}
+        }
+    }
+
+    pub fn meta(&self) -> Option<EventMeta> {
+        match self {
+            PumpEvent::TokenLaunch(launch) => launch.meta,
+            PumpEvent::Trade(trade) => trade.meta,
+            PumpEvent::WalletTrade(wallet_trade) => wallet_trade.trade.meta,
+            PumpEvent::Migration(migration) => migration.meta,
+            PumpEvent::Candle(_) | PumpEvent::Unknown(_) => None,
+        }
+    }
+
+    // When the event happened as far as we know: its receipt time, or a candle's open time
+    pub fn event_time(&self) -> Option<DateTime<Utc>> {
+        match self {
+            PumpEvent::Candle(candle) => Some(candle.openTime),
+            _ => self.meta().map(|meta| meta.receivedAt),
+        }
+    }
+
+    pub fn mint(&self) -> Option<&str> {
+        match self {
+            PumpEvent::TokenLaunch(launch) => Some(&launch.mint),
+            PumpEvent::Trade(trade) => Some(&trade.mint),
+            PumpEvent::WalletTrade(wallet_trade) => Some(&wallet_trade.trade.mint),
+            PumpEvent::Migration(migration) => Some(&migration.mint),
+            PumpEvent::Candle(candle) => Some(&candle.mint),
+            PumpEvent::Unknown(_) => None,
+        }
+    }
+
+    // Dataset name used for Parquet partitions and JSONL output
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            PumpEvent::TokenLaunch(_) => "token_launch",
+            PumpEvent::Trade(_) => "trade",
+            PumpEvent::WalletTrade(_) => "wallet_trade",
+            PumpEvent::Migration(_) => "migration",
+            PumpEvent::Candle(_) => "candle",
+            PumpEvent::Unknown(_) => "unknown",
+        }
+    }
+
+    fn validate(&self) -> Result<(), serde_json::Error> {
+        let (signature, mint) = match self {
+            PumpEvent::TokenLaunch(launch) => (&launch.signature, &launch.mint),
+            PumpEvent::Trade(trade) => (&trade.signature, &trade.mint),
+            PumpEvent::WalletTrade(wallet_trade) => (&wallet_trade.trade.signature, &wallet_trade.trade.mint),
+            PumpEvent::Migration(migration) => (&migration.signature, &migration.mint),
+            PumpEvent::Candle(_) | PumpEvent::Unknown(_) => return Ok(()),
+        };
+
+        if signature.is_empty() {
+            return Err(de::Error::custom("empty signature"));
+        }
+        if mint.is_empty() {
+            return Err(de::Error::custom("empty mint"));
+        }
+        if let PumpEvent::Trade(trade) = self
+            && (trade.tokenAmount < 0.0 || trade.solAmount < 0.0)
+        {
+            return Err(de::Error::custom(format!("negative trade amount in {}", trade.signature)));
+        }
+
+        // Amounts are stored as exact decimals, so anything their columns can't hold is refused here
+        let (sol, tokens) = match self {
+            PumpEvent::TokenLaunch(launch) => (
+                vec![launch.solInPool, Some(launch.solAmount), Some(launch.marketCapSol)],
+                vec![launch.tokensInPool, Some(launch.initialBuy), launch.newTokenBalance],
+            ),
+            PumpEvent::Trade(trade) => (
+                vec![Some(trade.solAmount), trade.vSolInBondingCurve, Some(trade.marketCapSol)],
+                vec![Some(trade.tokenAmount), trade.newTokenBalance, trade.vTokensInBondingCurve],
+            ),
+            _ => return Ok(()),
+        };
+        let sol_limit = 10f64.powi((SOL_PRECISION as i8 - SOL_SCALE) as i32);
+        let token_limit = 10f64.powi((TOKEN_PRECISION as i8 - TOKEN_SCALE) as i32);
+        let in_range = |values: Vec<Option<f64>>, limit: f64| {
+            values.into_iter().flatten().all(|value| value.is_finite() && value.abs() < limit)
+        };
+        if !in_range(sol, sol_limit) || !in_range(tokens, token_limit) {
+            return Err(de::Error::custom(format!("amount out of range in {}", signature)));
+        }
+        Ok(())
+    }
+}
+
+fn parse_payload<T: for<'de> Deserialize<'de>>(raw: &Value, kind: &str) -> Result<T, serde_json::Error> {
+    T::deserialize(raw).map_err(|e| de::Error::custom(format!("invalid {} event: {}", kind, e)))
+}
+
+impl<'de> Deserialize<'de> for PumpEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Value::deserialize(deserializer)?;
+        PumpEvent::from_value(raw).map_err(de::Error::custom)
+    }
+}
@@ -1,57 +1,134 @@
-#![allow(non_snake_case)]
-#![allow(dead_code)]
-// use arrow::array::RecordBatch;
-use tokio_tungstenite::connect_async;
-use futures::{SinkExt, StreamExt};
-// use serde::{Deserialize};
-use serde_json::{json};
-use url::Url;
-use tracing:: warn;
+use std::time::{Duration, Instant};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+use crate::auto_follow::AutoFollow;
+use crate::config::Config;
+use crate::event::{EventSequencer, PumpEvent};
+use crate::journal::JournalWriter;
 use crate::process_data::PumpPipeline;
+use crate::source::{build_source, EventSource};
+use crate::subscriptions::SubscriptionConfig;
 
-pub async fn ingest_ws_stream() {
-    let mut pipeline = PumpPipeline::new("./pump_data", 2).unwrap();
-    
+const EVICTION_INTERVAL: Duration = Duration::from_secs(30);
 
-    let url = Url::parse("wss://pumpportal.fun/api/data").unwrap();
-    let (mut ws_stream, _) = connect_async(url).await.expect("Failed to connect");
+// Track launches and trades for auto-follow, updating the source's trade subscriptions
+async fn apply_auto_follow(
+    auto_follow: &mut AutoFollow,
+    subscriptions: &SubscriptionConfig,
+    source: &mut dyn EventSource,
+    event: &PumpEvent,
+) {
+    let now = Instant::now();
 
-    let sub_msg = json!({
-        "method": "subscribeNewToken"
-    });
+    match event {
+        PumpEvent::TokenLaunch(launch) if !subscriptions.token_trades.contains(&launch.mint) => {
+            let mint = launch.mint.as_str();
+            let Some(evicted) = auto_follow.follow(mint, now) else {
+                return;
+            };
 
-    ws_stream.send(tokio_tungstenite::tungstenite::Message::Text(sub_msg.to_string()))
-        .await
-        .expect("Failed to send subscription");
+            info!("Auto-following trades for {} ({} tracked)", mint, auto_follow.len());
+            if !evicted.is_empty() {
+                source.unsubscribe_token_trades(&evicted).await;
+            }
+            source.subscribe_token_trades(&[mint.to_string()]).await;
+        }
+        PumpEvent::Trade(trade) => {
+            auto_follow.touch(&trade.mint, now);
+        }
+        _ => {}
+    }
+}
 
-    
-    let sub_msg = json!({
-        "method": "subscribeTokenTrade",
-        "keys": ["6bfrXdoo8nZFosAER94ihMz7a4rSwu6A8ismAuVtpump",
-            "9BadoUuov35qa2fujLdyqDYgMfCDuWuwDhJQ25yrpump"
-        ]
-    });
+pub async fn ingest_events(config: Config) {
+    let postgres = PumpPipeline::connect_postgres(&config).await.expect("Failed to connect to Postgres");
+    let mut pipeline = PumpPipeline::new(&config, postgres).unwrap();
+    let mut source = build_source(&config).await.expect("Failed to start event source");
+    let mut journal = JournalWriter::new(&config.storage_path, config.journal.clone()).unwrap();
+    let mut auto_follow = AutoFollow::new(config.auto_follow.clone());
+    let mut last_eviction = Instant::now();
+    let mut sequencer = EventSequencer::new();
+    info!("Ingest session {}", sequencer.session_id());
 
-    ws_stream.send(tokio_tungstenite::tungstenite::Message::Text(sub_msg.to_string()))
-        .await
-        .expect("Failed to send subscription");
+    let mut flush_timer = tokio::time::interval(pipeline.flush_interval());
+    flush_timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        let raw = tokio::select! {
+            _ = &mut shutdown => {
+                info!("Shutdown requested, stopping ingest");
+                break;
+            }
+            _ = flush_timer.tick() => {
+                pipeline.tick().await;
+                continue;
+            }
+            raw = source.next_event() => raw,
+        };
+        let Some(raw) = raw else {
+            info!("Event source exhausted");
+            break;
+        };
 
+        let meta = sequencer.next(raw.received_at);
+        if source.is_live()
+            && let Err(e) = journal.append(&meta, &raw.text)
+        {
+            warn!("Failed to journal frame: {:?}", e);
+        }
 
-    let (_, mut read) = ws_stream.split();
-    while let Some(msg) = read.next().await {
-        let msg = msg.expect("Failed to receive message");
-        let text = msg.to_text().expect("Expected message to be text");
         // println!("Received message: {}", text);
-        let text_json: serde_json::Value = match serde_json::from_str(text) {
-            Ok(val) => val,
+        let mut event: PumpEvent = match serde_json::from_str(&raw.text) {
+            Ok(event) => event,
             Err(e) => {
-                warn!("Failed to parse JSON: {:?}\nText: {:?}", e, text);
+                warn!("Failed to parse event: {:?}\nText: {:?}", e, raw.text);
+                pipeline.dead_letter(&raw.text, &format!("parse error: {}", e), raw.received_at).await;
                 continue;
             }
         };
+        event.set_meta(meta);
 
-       pipeline.process_data(text_json.clone()).await.expect("Failed to process data");
-    };
+        if auto_follow.enabled() {
+            apply_auto_follow(&mut auto_follow, &config.subscriptions, source.as_mut(), &event).await;
+
+            if last_eviction.elapsed() >= EVICTION_INTERVAL {
+                last_eviction = Instant::now();
+                let expired = auto_follow.evict_expired(last_eviction);
+                if !expired.is_empty() {
+                    info!("Unfollowing {} inactive mints", expired.len());
+                    source.unsubscribe_token_trades(&expired).await;
+                }
+            }
+        }
+
+       pipeline.process_data(event).await.expect("Failed to process data");
+    }
 
-    
+    // Drain everything before closing the pool so the last rows reach Postgres
+    if let Err(e) = pipeline.flush_all().await {
+        warn!("Failed to flush buffers on shutdown: {:?}", e);
+    }
+    if let Err(e) = journal.finish() {
+        warn!("Failed to close journal file: {:?}", e);
+    }
+    pipeline.close().await;
+    info!("Ingest stopped");
+}
+
+// Resolves on Ctrl-C (SIGINT) or SIGTERM
+async fn shutdown_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl-C: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate.recv() => {}
+    }
 }
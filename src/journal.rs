@@ -0,0 +1,225 @@
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, create_dir_all, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::event::{EventMeta, EventSequencer, PumpEvent};
+use crate::process_data::PumpPipeline;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JournalConfig {
+    pub enabled: bool,
+    // Rotate after this many uncompressed bytes or seconds, whichever comes first
+    pub max_file_bytes: u64,
+    pub max_file_age_secs: u64,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_file_bytes: 64 * 1024 * 1024,
+            max_file_age_secs: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub received_at: DateTime<Utc>,
+    // Absent in journals written before events carried receipt metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<uuid::Uuid>,
+    pub raw: String,
+}
+
+struct ActiveFile {
+    encoder: GzEncoder<BufWriter<File>>,
+    path: PathBuf,
+    opened_at: Instant,
+    bytes: u64,
+}
+
+// Append-only gzip JSONL journal of raw websocket frames under <storage>/journal
+pub struct JournalWriter {
+    dir: PathBuf,
+    config: JournalConfig,
+    current: Option<ActiveFile>,
+}
+
+impl JournalWriter {
+    pub fn new(base_path: &str, config: JournalConfig) -> Result<Self, Box<dyn Error>> {
+        let dir = journal_dir(base_path);
+        create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            config,
+            current: None,
+        })
+    }
+
+    pub fn append(&mut self, meta: &EventMeta, raw: &str) -> Result<(), Box<dyn Error>> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let needs_rotation = self.current.as_ref().is_some_and(|file| {
+            file.bytes >= self.config.max_file_bytes
+                || file.opened_at.elapsed() >= Duration::from_secs(self.config.max_file_age_secs)
+        });
+        if needs_rotation {
+            self.finish()?;
+        }
+
+        if self.current.is_none() {
+            let path = self.dir.join(format!("journal_{}.jsonl.gz", meta.receivedAt.format("%Y%m%d_%H%M%S%6f")));
+            let file = File::create(&path)?;
+            info!("Opened journal file {}", path.display());
+            self.current = Some(ActiveFile {
+                encoder: GzEncoder::new(BufWriter::new(file), Compression::default()),
+                path,
+                opened_at: Instant::now(),
+                bytes: 0,
+            });
+        }
+
+        let entry = JournalEntry {
+            received_at: meta.receivedAt,
+            sequence: Some(meta.sequence),
+            session_id: Some(meta.sessionId),
+            raw: raw.to_string(),
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let file = self.current.as_mut().expect("journal file is open");
+        file.encoder.write_all(line.as_bytes())?;
+        file.bytes += line.len() as u64;
+        Ok(())
+    }
+
+    // Write the gzip trailer and close the active file
+    pub fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(file) = self.current.take() {
+            let mut writer = file.encoder.finish()?;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+            info!("Closed journal file {} ({} bytes raw)", file.path.display(), file.bytes);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for JournalWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            warn!("Failed to close journal file: {:?}", e);
+        }
+    }
+}
+
+pub fn journal_dir(base_path: &str) -> PathBuf {
+    Path::new(base_path).join("journal")
+}
+
+pub fn journal_files(base_path: &str) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let dir = journal_dir(base_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.to_string_lossy().ends_with(".jsonl.gz"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// Read every complete entry; a file cut short by a crash yields what was written
+pub fn read_journal(path: &Path) -> Result<Vec<JournalEntry>, Box<dyn Error>> {
+    let reader = BufReader::new(MultiGzDecoder::new(File::open(path)?));
+    let mut entries = Vec::new();
+
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Journal {} ends early: {:?}", path.display(), e);
+                break;
+            }
+        };
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => warn!("Skipping corrupt journal entry in {}: {:?}", path.display(), e),
+        }
+    }
+    Ok(entries)
+}
+
+// Feed journaled frames through the pipeline; speed None replays as fast as possible
+pub async fn replay_journal(config: Config, files: Vec<PathBuf>, speed: Option<f64>) -> Result<(), Box<dyn Error>> {
+    let files = if files.is_empty() {
+        journal_files(&config.storage_path)?
+    } else {
+        files
+    };
+    let postgres = PumpPipeline::connect_postgres(&config).await?;
+    let mut pipeline = PumpPipeline::new(&config, postgres)?;
+    let mut previous: Option<DateTime<Utc>> = None;
+    let (mut replayed, mut failed) = (0usize, 0usize);
+    // Only for entries journaled without metadata; the rest keep their original stamps
+    let mut sequencer = EventSequencer::new();
+
+    for path in files {
+        info!("Replaying journal {}", path.display());
+
+        for entry in read_journal(&path)? {
+            if let (Some(speed), Some(previous)) = (speed, previous) {
+                let gap = (entry.received_at - previous).to_std().unwrap_or_default();
+                if speed > 0.0 && !gap.is_zero() {
+                    tokio::time::sleep(gap.div_f64(speed)).await;
+                }
+            }
+            previous = Some(entry.received_at);
+
+            let meta = match (entry.sequence, entry.session_id) {
+                (Some(sequence), Some(session_id)) => EventMeta {
+                    receivedAt: entry.received_at,
+                    sequence,
+                    sessionId: session_id,
+                },
+                _ => sequencer.next(entry.received_at),
+            };
+
+            match serde_json::from_str::<PumpEvent>(&entry.raw) {
+                // Already dead-lettered when first received
+                Ok(PumpEvent::Unknown(_)) => {}
+                Ok(mut event) => {
+                    event.set_meta(meta);
+                    pipeline.process_data(event).await?;
+                    replayed += 1;
+                }
+                Err(e) => {
+                    warn!("Failed to parse journaled frame: {:?}\nText: {:?}", e, entry.raw);
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    pipeline.flush_all().await?;
+    eprintln!("✅ Replayed {} frames ({} failed to parse)", replayed, failed);
+    Ok(())
+}
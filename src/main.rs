@@ -1,13 +1,68 @@
-
 mod ingest;
 mod process_data;
 mod arrow;
 mod postgres_db;
+mod postgres_writer;
 mod parquet_storage;
-use ingest::ingest_ws_stream;
+mod parquet_writer;
+mod config;
+mod subscriptions;
+mod auto_follow;
+mod event;
+mod dead_letter;
+mod journal;
+mod source;
+mod sink;
+mod schema_migrations;
+mod candles;
+mod bonding_curve;
+mod compaction;
+use clap::Parser;
+use compaction::compact_storage;
+use config::{Cli, Command, Config, MigrateCommand};
+use dead_letter::reprocess_dead_letters;
+use journal::replay_journal;
+use ingest::ingest_events;
+use postgres_db::PumpPostgres;
 
 #[tokio::main]
 async fn main() {
-    ingest_ws_stream().await;
+    // Logs and status lines go to stderr so stdout carries only the stdout sink's JSONL
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+
+    let cli = Cli::parse();
+    let config = Config::load(&cli).expect("Failed to load config");
+    match cli.command.unwrap_or(Command::Ingest) {
+        Command::Ingest => ingest_events(config).await,
+        Command::ReprocessDeadLetters => {
+            reprocess_dead_letters(config).await.expect("Failed to reprocess dead letters")
+        }
+        Command::Replay { files, speed, max_speed } => {
+            let speed = if max_speed { None } else { Some(speed) };
+            replay_journal(config, files, speed).await.expect("Failed to replay journal")
+        }
+        Command::Compact { target_file_mb, max_file_rows, dry_run } => {
+            compact_storage(&config.storage_path, &config.parquet, target_file_mb * 1024 * 1024, max_file_rows, dry_run)
+                .expect("Failed to compact Parquet files")
+        }
+        Command::Migrate { action } => {
+            let postgres = PumpPostgres::connect(&config.postgres).await.expect("Failed to connect to Postgres");
+            match action {
+                MigrateCommand::Up => {
+                    let applied = postgres.migrate_up().await.expect("Failed to apply migrations");
+                    println!("✅ Applied {} migrations", applied);
+                    if config.postgres.timescale.enabled {
+                        postgres
+                            .setup_timescale(&config.postgres.timescale)
+                            .await
+                            .expect("Failed to set up TimescaleDB");
+                    }
+                }
+                MigrateCommand::Status => {
+                    postgres.print_migration_status().await.expect("Failed to read migration status")
+                }
+            }
+        }
+    }
 
 }
@@ -1,37 +1,215 @@
+use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
 use parquet::arrow::ArrowWriter;
 use parquet::file::properties::WriterProperties;
-use parquet::basic::{Compression, Encoding};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use std::error::Error;
-use std::fs::{File, create_dir_all};
-use chrono; // Add chrono to your Cargo.toml
-use uuid;   // Add uuid to your Cargo.toml
+use std::collections::HashMap;
+use std::fs::{self, File, TryLockError, create_dir_all};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::arrow::{dataset_schemas, EventBatchBuilder, SCHEMA_VERSION};
+use crate::event::PumpEvent;
+use crate::parquet_writer::WriterSettings;
+use crate::sink::EventSink;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ParquetConfig {
+    // Directory layout under storage_path; placeholders are filled per event
+    pub partition_template: String,
+    // Characters of the mint used for {mint_prefix}
+    pub mint_prefix_len: usize,
+    // Writer settings for every dataset
+    pub writer: WriterSettings,
+    // Overrides per dataset, e.g. [parquet.datasets.trade]
+    pub datasets: HashMap<String, WriterSettings>,
+}
+
+impl Default for ParquetConfig {
+    fn default() -> Self {
+        Self {
+            partition_template: "event_type={event_type}/date={date}/hour={hour}".to_string(),
+            mint_prefix_len: 2,
+            writer: WriterSettings::default(),
+            datasets: HashMap::new(),
+        }
+    }
+}
+
+impl ParquetConfig {
+    pub fn writer_settings(&self, dataset: &str) -> WriterSettings {
+        match self.datasets.get(dataset) {
+            Some(overrides) => self.writer.overlay(overrides),
+            None => self.writer.clone(),
+        }
+    }
+
+    // Catch bad writer settings at startup rather than on the first flush
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let schemas = dataset_schemas();
+        for dataset in self.datasets.keys() {
+            if !schemas.iter().any(|(name, _)| name == dataset) {
+                return Err(format!("Unknown dataset parquet.datasets.{}", dataset).into());
+            }
+        }
+        for (dataset, schema) in &schemas {
+            let settings = self.writer_settings(dataset);
+            let columns = settings
+                .bloom_filter_columns
+                .iter()
+                .flatten()
+                .chain(settings.column_encodings.iter().flat_map(|encodings| encodings.keys()));
+            for column in columns {
+                if !schemas.iter().any(|(_, schema)| schema.field_with_name(column).is_ok()) {
+                    return Err(format!("Unknown column {} in Parquet writer settings", column).into());
+                }
+            }
+            settings.properties(dataset, schema, None, 1)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PartitionKey {
+    EventType,
+    Date,
+    Year,
+    Month,
+    Day,
+    Hour,
+    MintPrefix,
+}
+
+#[derive(Debug, Clone)]
+enum PartitionSegment {
+    Literal(String),
+    Key(PartitionKey),
+}
+
+// A parsed partition_template, rendered to a relative directory per event
+#[derive(Debug, Clone)]
+pub struct PartitionTemplate {
+    segments: Vec<PartitionSegment>,
+    mint_prefix_len: usize,
+}
+
+impl PartitionTemplate {
+    pub fn parse(config: &ParquetConfig) -> Result<Self, Box<dyn Error>> {
+        let template = config.partition_template.trim_matches('/');
+        if template.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+            return Err(format!("Invalid parquet.partition_template {:?}", config.partition_template).into());
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(PartitionSegment::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("Unclosed placeholder in parquet.partition_template {:?}", template))?;
+            let key = match &rest[start + 1..start + end] {
+                "event_type" => PartitionKey::EventType,
+                "date" => PartitionKey::Date,
+                "year" => PartitionKey::Year,
+                "month" => PartitionKey::Month,
+                "day" => PartitionKey::Day,
+                "hour" => PartitionKey::Hour,
+                "mint_prefix" => PartitionKey::MintPrefix,
+                other => return Err(format!("Unknown placeholder {{{}}} in parquet.partition_template", other).into()),
+            };
+            segments.push(PartitionSegment::Key(key));
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(PartitionSegment::Literal(rest.to_string()));
+        }
+
+        // Each directory must hold a single schema
+        if !segments.iter().any(|segment| matches!(segment, PartitionSegment::Key(PartitionKey::EventType))) {
+            return Err("parquet.partition_template must contain {event_type}".into());
+        }
+        Ok(Self {
+            segments,
+            mint_prefix_len: config.mint_prefix_len,
+        })
+    }
+
+    // Partition keys come from the event's own timestamp, not the time it is written
+    pub fn render(&self, event: &PumpEvent) -> String {
+        let time: DateTime<Utc> = event.event_time().unwrap_or_else(Utc::now);
+        let mut path = String::new();
+        for segment in &self.segments {
+            match segment {
+                PartitionSegment::Literal(literal) => path.push_str(literal),
+                PartitionSegment::Key(PartitionKey::EventType) => path.push_str(event.event_type()),
+                PartitionSegment::Key(PartitionKey::Date) => path.push_str(&time.format("%Y-%m-%d").to_string()),
+                PartitionSegment::Key(PartitionKey::Year) => path.push_str(&time.format("%Y").to_string()),
+                PartitionSegment::Key(PartitionKey::Month) => path.push_str(&time.format("%m").to_string()),
+                PartitionSegment::Key(PartitionKey::Day) => path.push_str(&time.format("%d").to_string()),
+                PartitionSegment::Key(PartitionKey::Hour) => path.push_str(&time.format("%H").to_string()),
+                PartitionSegment::Key(PartitionKey::MintPrefix) => {
+                    let mint = event.mint().unwrap_or("unknown");
+                    path.extend(mint.chars().take(self.mint_prefix_len));
+                }
+            }
+        }
+        path
+    }
+}
 
 pub struct ParquetStorage {
     base_path: String,
+    config: ParquetConfig,
 }
 
 impl ParquetStorage {
-    pub fn new(base_path: String) -> Result<Self, Box<dyn Error>> {
+    pub fn new(base_path: String, config: ParquetConfig) -> Result<Self, Box<dyn Error>> {
+        config.validate()?;
         create_dir_all(&base_path)?;
-        Ok(Self { base_path })
+        let storage = Self { base_path, config };
+        storage.quarantine_orphans()?;
+        Ok(storage)
+    }
+
+    // Temp files left by a crash mid-write are moved aside so readers never see them;
+    // those of a writer that is still running (another ingest, a compaction) are left alone
+    fn quarantine_orphans(&self) -> Result<(), Box<dyn Error>> {
+        let base = Path::new(&self.base_path);
+        let quarantine = base.join(QUARANTINE_DIR);
+        let mut orphans = Vec::new();
+        find_temp_files(base, &quarantine, &mut orphans)?;
+        if orphans.is_empty() {
+            return Ok(());
+        }
+
+        create_dir_all(&quarantine)?;
+        for orphan in &orphans {
+            let relative = orphan.strip_prefix(base)?.to_string_lossy().replace(std::path::MAIN_SEPARATOR, "_");
+            fs::rename(orphan, quarantine.join(relative))?;
+        }
+        warn!("Moved {} incomplete Parquet files to {}", orphans.len(), quarantine.display());
+        Ok(())
     }
 
-    pub fn write_batch(&self, batches: &[RecordBatch], event_type: &str) -> Result<String, Box<dyn Error>> {
-        // Check for empty batches
-        if batches.is_empty() {
-            return Err("No batches to write".into());
+    // Writes a dataset's rows into <base>/<partition>; returns the file, or None when there were no rows to write
+    pub fn write_batch(&self, batches: &[RecordBatch], dataset: &str, partition: &str) -> Result<Option<String>, Box<dyn Error>> {
+        // Nothing to write is not an error, so flushes can run on empty buffers
+        if batches.iter().all(|batch| batch.num_rows() == 0) {
+            return Ok(None);
         }
 
         let now = chrono::Utc::now();
-        let dir_path = format!("{}/{}/{}", 
-            self.base_path, 
-            now.format("%Y/%m/%d"), 
-            event_type
-        );
+        let dir_path = format!("{}/{}", self.base_path, partition);
         create_dir_all(&dir_path)?;
 
-        // Fixed: Create just the filename, not the full path
         let file_name = format!("batch_{}_{}.parquet", 
             now.format("%H%M%S"),
             uuid::Uuid::new_v4().simple()
@@ -40,25 +218,263 @@ impl ParquetStorage {
         // Construct the full file path
         let file_path = format!("{}/{}", dir_path, file_name);
 
-        let props = WriterProperties::builder()
-            .set_compression(Compression::SNAPPY)
-            .set_encoding(Encoding::PLAIN)
-            .set_dictionary_enabled(true)
-            .build();
+        let props = self.config.writer_settings(dataset).properties(
+            dataset,
+            &batches[0].schema(),
+            Some(SCHEMA_VERSION.to_string()),
+            batches.iter().map(|batch| batch.num_rows()).sum(),
+        )?;
+
+        // Written under a hidden temp name and renamed once complete and on disk
+        let tmp_path = format!("{}/{}", dir_path, temp_name(&file_name));
+        let temp = create_temp(Path::new(&tmp_path))?;
+        let written = temp
+            .try_clone()
+            .map_err(Into::into)
+            .and_then(|file| write_file(file, batches[0].schema(), batches.iter().cloned().map(Ok), props));
+        let total_rows = match written {
+            Ok(rows) => rows,
+            Err(e) => {
+                let _ = fs::remove_file(&tmp_path);
+                return Err(e);
+            }
+        };
+        fs::rename(&tmp_path, &file_path)?;
+        drop(temp);
+        sync_dir(Path::new(&dir_path))?;
+
+        eprintln!("✅ Wrote {} rows to {}", total_rows, file_path);
+        Ok(Some(file_path))
+    }
+
+}
+
+const QUARANTINE_DIR: &str = "_quarantine";
+// Where file locks aren't supported, a temp file this long untouched is taken as abandoned
+const ORPHAN_MIN_AGE: Duration = Duration::from_secs(15 * 60);
+
+// Hidden name a Parquet file is written under before the rename
+pub fn temp_name(file_name: &str) -> String {
+    format!(".{}.tmp", file_name)
+}
+
+// Create a temp file and hold an exclusive lock on it; the writer keeps the returned handle
+// until the file is renamed, so a startup sweep can tell a live write from an abandoned one
+pub fn create_temp(path: &Path) -> Result<File, Box<dyn Error>> {
+    let file = File::create(path)?;
+    file.lock()?;
+    Ok(file)
+}
 
-        let file = File::create(&file_path)?;
-        println!("✅ Created file {}", file_path);
-        let mut writer = ArrowWriter::try_new(file, batches[0].schema(), Some(props))?;
+// Renames and deletes are only durable once their directory is synced
+pub fn sync_dir(dir: &Path) -> Result<(), Box<dyn Error>> {
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
 
-        let mut total_rows = 0;
-        for batch in batches {
-            writer.write(batch)?;
-            total_rows += batch.num_rows();
+// Write and fsync one Parquet file, taking batches as they are produced; returns the number of rows written
+pub fn write_file(
+    file: File,
+    schema: SchemaRef,
+    batches: impl IntoIterator<Item = Result<RecordBatch, Box<dyn Error>>>,
+    props: WriterProperties,
+) -> Result<usize, Box<dyn Error>> {
+    let mut writer = ArrowWriter::try_new(file, schema, Some(props))?;
+
+    let mut total_rows = 0;
+    for batch in batches {
+        let batch = batch?;
+        writer.write(&batch)?;
+        total_rows += batch.num_rows();
+    }
+
+    let file = writer.into_inner()?;
+    file.sync_all()?;
+    Ok(total_rows)
+}
+
+fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') && name.ends_with(".tmp") && name.contains(".parquet."))
+}
+
+// A temp file nobody holds the lock on has no writer left; the lock goes with the process,
+// so unlike a pid it can't be mistaken for a later process's
+fn is_orphan(path: &Path) -> bool {
+    // A file gone in the meantime was renamed by its writer
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    match file.try_lock() {
+        Ok(()) => return true,
+        Err(TryLockError::WouldBlock) => return false,
+        Err(TryLockError::Error(_)) => {}
+    }
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= ORPHAN_MIN_AGE)
+}
+
+fn find_temp_files(dir: &Path, skip: &Path, found: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if path != skip {
+                find_temp_files(&path, skip, found)?;
+            }
+        } else if is_temp_file(&path) && is_orphan(&path) {
+            found.push(path);
         }
-        
-        writer.close()?;
-        println!("✅ Wrote {} rows to {}", total_rows, file_path);
-        Ok(file_path)
     }
+    Ok(())
+}
 
+// Appends events into per-partition column builders and writes one batch, and
+// so one row group, every buffer_size rows
+pub struct ParquetSink {
+    storage: ParquetStorage,
+    template: PartitionTemplate,
+    builders: HashMap<String, EventBatchBuilder>,
+    // Finished batches per partition, with their event type, kept until a write succeeds
+    pending: HashMap<String, (&'static str, Vec<RecordBatch>)>,
+    buffer_size: usize,
+}
+
+impl ParquetSink {
+    pub fn new(base_path: &str, buffer_size: usize, config: &ParquetConfig) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            storage: ParquetStorage::new(base_path.to_string(), config.clone())?,
+            template: PartitionTemplate::parse(config)?,
+            builders: HashMap::new(),
+            pending: HashMap::new(),
+            buffer_size,
+        })
+    }
+
+    // Write a partition's pending batches as one file; on failure they stay queued for the next try
+    fn write_pending(&mut self, partition: &str) -> Result<(), Box<dyn Error>> {
+        if let Some((event_type, batches)) = self.pending.get(partition) {
+            self.storage.write_batch(batches, event_type, partition)?;
+            self.pending.remove(partition);
+        }
+        Ok(())
+    }
+
+    fn queue(&mut self, partition: &str, event_type: &'static str, batch: RecordBatch) {
+        self.pending
+            .entry(partition.to_string())
+            .or_insert_with(|| (event_type, Vec::new()))
+            .1
+            .push(batch);
+    }
+}
+
+#[async_trait(?Send)]
+impl EventSink for ParquetSink {
+    fn name(&self) -> &'static str {
+        "parquet"
+    }
+
+    async fn write(&mut self, event: &PumpEvent) -> Result<(), Box<dyn Error>> {
+        let partition = self.template.render(event);
+        if !self.builders.contains_key(&partition) {
+            let builder = EventBatchBuilder::for_event(event)
+                .ok_or_else(|| format!("No Parquet schema for {} events", event.event_type()))?;
+            self.builders.insert(partition.clone(), builder);
+        }
+        let builder = self.builders.get_mut(&partition).expect("builder was just inserted");
+        builder.append(event)?;
+
+        if builder.len() >= self.buffer_size {
+            let event_type = builder.event_type();
+            let batch = builder.finish()?;
+            self.queue(&partition, event_type, batch);
+            self.write_pending(&partition)?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut finished = Vec::new();
+        for (partition, builder) in self.builders.iter_mut() {
+            if !builder.is_empty() {
+                finished.push((partition.clone(), builder.event_type(), builder.finish()?));
+            }
+        }
+        // Partitions for past hours would otherwise stay open forever
+        self.builders.clear();
+        for (partition, event_type, batch) in finished {
+            self.queue(&partition, event_type, batch);
+        }
+
+        // Every partition gets its chance even when an earlier one fails
+        let mut failed = Vec::new();
+        let partitions: Vec<String> = self.pending.keys().cloned().collect();
+        for partition in partitions {
+            if let Err(e) = self.write_pending(&partition) {
+                warn!("Failed to write Parquet partition {}: {}", partition, e);
+                failed.push(partition);
+            }
+        }
+        if !failed.is_empty() {
+            return Err(format!("Failed to write Parquet partitions: {}", failed.join(", ")).into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventMeta, Migration};
+    use chrono::TimeZone;
+
+    fn template(partition_template: &str) -> Result<PartitionTemplate, Box<dyn Error>> {
+        PartitionTemplate::parse(&ParquetConfig {
+            partition_template: partition_template.to_string(),
+            ..ParquetConfig::default()
+        })
+    }
+
+    fn migration() -> PumpEvent {
+        PumpEvent::Migration(Migration {
+            signature: "sig".to_string(),
+            mint: "AbcMint".to_string(),
+            txType: "migrate".to_string(),
+            pool: None,
+            meta: Some(EventMeta {
+                receivedAt: Utc.with_ymd_and_hms(2026, 3, 9, 7, 30, 0).unwrap(),
+                sequence: 0,
+                sessionId: uuid::Uuid::nil(),
+            }),
+        })
+    }
+
+    #[test]
+    fn default_template_renders_hive_partitions() {
+        let template = template(&ParquetConfig::default().partition_template).unwrap();
+        assert_eq!(template.render(&migration()), "event_type=migration/date=2026-03-09/hour=07");
+    }
+
+    #[test]
+    fn template_renders_every_placeholder_and_trims_slashes() {
+        let template = template("/{event_type}/{year}/{month}/{day}/mint={mint_prefix}/").unwrap();
+        assert_eq!(template.render(&migration()), "migration/2026/03/09/mint=Ab");
+    }
+
+    #[test]
+    fn template_requires_event_type() {
+        assert!(template("date={date}").is_err());
+    }
+
+    #[test]
+    fn template_rejects_bad_placeholders_and_paths() {
+        assert!(template("{event_type}/{minute}").is_err());
+        assert!(template("{event_type}/{date").is_err());
+        assert!(template("{event_type}//{date}").is_err());
+        assert!(template("{event_type}/../{date}").is_err());
+    }
 }
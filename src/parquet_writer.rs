@@ -0,0 +1,208 @@
+use arrow::datatypes::{DataType, Schema};
+use parquet::basic::{BrotliLevel, Compression, Encoding, GzipLevel, ZstdLevel};
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::{EnabledStatistics, WriterProperties};
+use parquet::schema::types::ColumnPath;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::arrow::{DATASET_KEY, SCHEMA_VERSION_KEY};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParquetCompression {
+    Uncompressed,
+    Snappy,
+    Gzip,
+    Lz4,
+    Zstd,
+    Brotli,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatisticsLevel {
+    None,
+    // Min/max per column chunk
+    Chunk,
+    // Also per page, with column and offset indexes for page pruning
+    Page,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParquetEncoding {
+    Plain,
+    DeltaBinaryPacked,
+    DeltaLengthByteArray,
+    DeltaByteArray,
+    ByteStreamSplit,
+}
+
+impl ParquetEncoding {
+    fn encoding(&self) -> Encoding {
+        match self {
+            ParquetEncoding::Plain => Encoding::PLAIN,
+            ParquetEncoding::DeltaBinaryPacked => Encoding::DELTA_BINARY_PACKED,
+            ParquetEncoding::DeltaLengthByteArray => Encoding::DELTA_LENGTH_BYTE_ARRAY,
+            ParquetEncoding::DeltaByteArray => Encoding::DELTA_BYTE_ARRAY,
+            ParquetEncoding::ByteStreamSplit => Encoding::BYTE_STREAM_SPLIT,
+        }
+    }
+
+    // Whether Parquet can store a column of this Arrow type with the encoding
+    fn supports(&self, data_type: &DataType) -> bool {
+        let integer = matches!(data_type, DataType::Int64 | DataType::Timestamp(_, _));
+        let bytes = matches!(data_type, DataType::Utf8 | DataType::Dictionary(_, _));
+        let fixed = matches!(data_type, DataType::Decimal128(_, _));
+        match self {
+            ParquetEncoding::Plain => true,
+            ParquetEncoding::DeltaBinaryPacked => integer,
+            ParquetEncoding::DeltaLengthByteArray => bytes,
+            ParquetEncoding::DeltaByteArray => bytes || fixed,
+            ParquetEncoding::ByteStreamSplit => integer || fixed || matches!(data_type, DataType::Float64),
+        }
+    }
+}
+
+// Writer settings for one dataset; unset fields fall back to [parquet.writer], then the defaults
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WriterSettings {
+    // Default snappy
+    pub compression: Option<ParquetCompression>,
+    // zstd 1-22, gzip 0-9, brotli 0-11
+    pub compression_level: Option<u32>,
+    // Default 1048576
+    pub max_row_group_rows: Option<usize>,
+    // Default 1 MiB
+    pub data_page_size_bytes: Option<usize>,
+    // Default true
+    pub dictionary: Option<bool>,
+    // Default page
+    pub statistics: Option<StatisticsLevel>,
+    // Columns to write bloom filters for, where the dataset has them
+    pub bloom_filter_columns: Option<Vec<String>>,
+    // False positive rate of those bloom filters, default 0.05
+    pub bloom_filter_fpp: Option<f64>,
+    // Distinct values each bloom filter is sized for, default the rows per row group
+    pub bloom_filter_ndv: Option<u64>,
+    // DELTA_BINARY_PACKED for every integer and timestamp column, default false
+    pub delta_integers: Option<bool>,
+    // Explicit encoding per column, overriding delta_integers
+    pub column_encodings: Option<HashMap<String, ParquetEncoding>>,
+}
+
+impl WriterSettings {
+    // These settings with any field set in `over` taking its place
+    pub fn overlay(&self, over: &WriterSettings) -> WriterSettings {
+        WriterSettings {
+            compression: over.compression.or(self.compression),
+            compression_level: over.compression_level.or(self.compression_level),
+            max_row_group_rows: over.max_row_group_rows.or(self.max_row_group_rows),
+            data_page_size_bytes: over.data_page_size_bytes.or(self.data_page_size_bytes),
+            dictionary: over.dictionary.or(self.dictionary),
+            statistics: over.statistics.or(self.statistics),
+            bloom_filter_columns: over.bloom_filter_columns.clone().or_else(|| self.bloom_filter_columns.clone()),
+            bloom_filter_fpp: over.bloom_filter_fpp.or(self.bloom_filter_fpp),
+            bloom_filter_ndv: over.bloom_filter_ndv.or(self.bloom_filter_ndv),
+            delta_integers: over.delta_integers.or(self.delta_integers),
+            column_encodings: over.column_encodings.clone().or_else(|| self.column_encodings.clone()),
+        }
+    }
+
+    fn compression(&self) -> Result<Compression, Box<dyn Error>> {
+        let level = self.compression_level;
+        let compression = match self.compression.unwrap_or(ParquetCompression::Snappy) {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Lz4 => Compression::LZ4_RAW,
+            ParquetCompression::Gzip => Compression::GZIP(match level {
+                Some(level) => GzipLevel::try_new(level)?,
+                None => GzipLevel::default(),
+            }),
+            ParquetCompression::Brotli => Compression::BROTLI(match level {
+                Some(level) => BrotliLevel::try_new(level)?,
+                None => BrotliLevel::default(),
+            }),
+            ParquetCompression::Zstd => Compression::ZSTD(match level {
+                Some(level) => ZstdLevel::try_new(level as i32)?,
+                None => ZstdLevel::default(),
+            }),
+        };
+        Ok(compression)
+    }
+
+    // Properties for writing `rows` rows of `schema` as `dataset`; files are stamped with
+    // both so compaction can rewrite them with the same settings
+    pub fn properties(
+        &self,
+        dataset: &str,
+        schema: &Schema,
+        schema_version: Option<String>,
+        rows: usize,
+    ) -> Result<WriterProperties, Box<dyn Error>> {
+        let mut metadata = vec![KeyValue::new(DATASET_KEY.to_string(), dataset.to_string())];
+        if let Some(version) = schema_version {
+            metadata.push(KeyValue::new(SCHEMA_VERSION_KEY.to_string(), version));
+        }
+
+        let statistics = match self.statistics.unwrap_or(StatisticsLevel::Page) {
+            StatisticsLevel::None => EnabledStatistics::None,
+            StatisticsLevel::Chunk => EnabledStatistics::Chunk,
+            StatisticsLevel::Page => EnabledStatistics::Page,
+        };
+        let max_row_group_rows = self.max_row_group_rows.unwrap_or(1024 * 1024);
+        let mut builder = WriterProperties::builder()
+            .set_compression(self.compression()?)
+            .set_encoding(Encoding::PLAIN)
+            .set_dictionary_enabled(self.dictionary.unwrap_or(true))
+            .set_statistics_enabled(statistics)
+            .set_max_row_group_size(max_row_group_rows)
+            .set_data_page_size_limit(self.data_page_size_bytes.unwrap_or(1024 * 1024))
+            .set_key_value_metadata(Some(metadata));
+
+        // Filters sized for the parquet default of a million values would dwarf small files
+        let fpp = self.bloom_filter_fpp.unwrap_or(0.05);
+        let ndv = self.bloom_filter_ndv.unwrap_or(rows.clamp(1, max_row_group_rows) as u64);
+        for column in self.bloom_filter_columns.iter().flatten() {
+            if schema.field_with_name(column).is_ok() {
+                let path = ColumnPath::from(column.as_str());
+                builder = builder
+                    .set_column_bloom_filter_enabled(path.clone(), true)
+                    .set_column_bloom_filter_fpp(path.clone(), fpp)
+                    .set_column_bloom_filter_ndv(path, ndv);
+            }
+        }
+
+        let encodings = self.column_encodings.clone().unwrap_or_default();
+        for field in schema.fields() {
+            let encoding = match encodings.get(field.name()) {
+                Some(encoding) => *encoding,
+                None if self.delta_integers.unwrap_or(false)
+                    && ParquetEncoding::DeltaBinaryPacked.supports(field.data_type()) =>
+                {
+                    ParquetEncoding::DeltaBinaryPacked
+                }
+                None => continue,
+            };
+            if !encoding.supports(field.data_type()) {
+                return Err(format!(
+                    "{:?} encoding does not fit {} column {} ({})",
+                    encoding,
+                    dataset,
+                    field.name(),
+                    field.data_type()
+                )
+                .into());
+            }
+            // A dictionary would take precedence, leaving the encoding as a fallback only
+            let path = ColumnPath::from(field.name().as_str());
+            builder = builder
+                .set_column_dictionary_enabled(path.clone(), false)
+                .set_column_encoding(path, encoding.encoding());
+        }
+        Ok(builder.build())
+    }
+}
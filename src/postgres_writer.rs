@@ -0,0 +1,465 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::error::Error;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+use crate::bonding_curve::BondingCurveState;
+use crate::dead_letter::{DeadLetter, DeadLetterStore};
+use crate::event::{Candle, Migration, PumpEvent, TokenLaunch, Trade, WalletTrade};
+use crate::postgres_db::{PostgresConfig, PumpPostgres};
+use crate::sink::EventSink;
+
+// Commands queued for the writer before ingest waits on it; at live rates this covers
+// far longer than a write can stall before the database is marked unhealthy
+const QUEUE_CAPACITY: usize = 10_000;
+const HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+
+enum WriterCommand {
+    Event(Box<PumpEvent>),
+    BondingCurves(Vec<BondingCurveState>),
+    DeadLetter(DeadLetter),
+    Flush,
+    // Write what is left, dead-letter what can't be written, then stop
+    Finish(oneshot::Sender<()>),
+}
+
+// Errors that mean the database is unreachable rather than that it refused the rows
+fn is_connection_error(error: &(dyn Error + 'static)) -> bool {
+    matches!(
+        error.downcast_ref::<sqlx::Error>(),
+        Some(sqlx::Error::Io(_)) | Some(sqlx::Error::PoolTimedOut) | Some(sqlx::Error::PoolClosed) | Some(sqlx::Error::Tls(_))
+    )
+}
+
+#[derive(Debug, Default)]
+pub struct PostgresWriteStats {
+    pub rows_written: u64,
+    pub rows_skipped: u64,
+    // Rows Postgres refused, sent to the dead-letter store
+    pub rows_rejected: u64,
+    pub batches: u64,
+    pub flush_time: Duration,
+}
+
+impl PostgresWriteStats {
+    pub fn rows_per_sec(&self) -> f64 {
+        let secs = self.flush_time.as_secs_f64();
+        if secs == 0.0 { 0.0 } else { (self.rows_written + self.rows_skipped) as f64 / secs }
+    }
+}
+
+// A buffered row type and the multi-row insert that writes it
+trait BufferedRow: Serialize + Sized {
+    async fn insert(postgres: &PumpPostgres, rows: &[Self]) -> Result<u64, Box<dyn Error>>;
+
+    // When the row, or the trade it came from, was received
+    fn received_at(&self) -> Option<DateTime<Utc>>;
+}
+
+impl BufferedRow for TokenLaunch {
+    async fn insert(postgres: &PumpPostgres, rows: &[Self]) -> Result<u64, Box<dyn Error>> {
+        postgres.insert_token_launches(rows).await
+    }
+
+    fn received_at(&self) -> Option<DateTime<Utc>> {
+        self.meta.map(|meta| meta.receivedAt)
+    }
+}
+
+impl BufferedRow for Trade {
+    async fn insert(postgres: &PumpPostgres, rows: &[Self]) -> Result<u64, Box<dyn Error>> {
+        postgres.insert_trades(rows).await
+    }
+
+    fn received_at(&self) -> Option<DateTime<Utc>> {
+        self.meta.map(|meta| meta.receivedAt)
+    }
+}
+
+impl BufferedRow for WalletTrade {
+    async fn insert(postgres: &PumpPostgres, rows: &[Self]) -> Result<u64, Box<dyn Error>> {
+        postgres.insert_wallet_trades(rows).await
+    }
+
+    fn received_at(&self) -> Option<DateTime<Utc>> {
+        self.trade.meta.map(|meta| meta.receivedAt)
+    }
+}
+
+impl BufferedRow for Migration {
+    async fn insert(postgres: &PumpPostgres, rows: &[Self]) -> Result<u64, Box<dyn Error>> {
+        postgres.insert_migrations(rows).await
+    }
+
+    fn received_at(&self) -> Option<DateTime<Utc>> {
+        self.meta.map(|meta| meta.receivedAt)
+    }
+}
+
+impl BufferedRow for Candle {
+    async fn insert(postgres: &PumpPostgres, rows: &[Self]) -> Result<u64, Box<dyn Error>> {
+        postgres.insert_candles(rows).await
+    }
+
+    fn received_at(&self) -> Option<DateTime<Utc>> {
+        Some(self.lastTradeAt)
+    }
+}
+
+// Insert rows, halving any batch Postgres rejects until the offending rows are found and
+// dead-lettered; a connection error fails the whole call so the caller keeps the rows.
+// Inserts are idempotent, so rows already written by then are harmless to write again
+async fn insert_or_reject<T: BufferedRow>(
+    postgres: &PumpPostgres,
+    dead_letters: Option<&DeadLetterStore>,
+    rows: &[T],
+) -> Result<(u64, u64), String> {
+    let (mut inserted, mut rejected) = (0, 0);
+    let mut pending = vec![rows];
+    while let Some(batch) = pending.pop() {
+        if batch.is_empty() {
+            continue;
+        }
+        let error = match T::insert(postgres, batch).await {
+            Ok(count) => {
+                inserted += count;
+                continue;
+            }
+            Err(e) if is_connection_error(e.as_ref()) => return Err(e.to_string()),
+            Err(e) => e.to_string(),
+        };
+        if batch.len() == 1 {
+            dead_letter(dead_letters, &batch[0], &format!("postgres insert failed: {}", error));
+            rejected += 1;
+        } else {
+            let (first, second) = batch.split_at(batch.len() / 2);
+            pending.push(second);
+            pending.push(first);
+        }
+    }
+    Ok((inserted, rejected))
+}
+
+fn dead_letter<T: BufferedRow>(dead_letters: Option<&DeadLetterStore>, row: &T, reason: &str) {
+    let raw = match serde_json::to_string(row) {
+        Ok(raw) => raw,
+        Err(e) => {
+            warn!("Failed to serialize row for dead-lettering: {:?}", e);
+            return;
+        }
+    };
+    match dead_letters {
+        Some(store) => {
+            if let Err(e) = store.record(&DeadLetter::new(&raw, reason, row.received_at().unwrap_or_else(Utc::now))) {
+                warn!("Failed to record dead letter: {:?}", e);
+            }
+        }
+        None => warn!("Dropped row ({}): {}", reason, raw),
+    }
+}
+
+// Dead-letter up to `count` of the oldest rows; returns how many went
+fn spill<T: BufferedRow>(dead_letters: Option<&DeadLetterStore>, rows: &mut Vec<T>, count: usize, reason: &str) -> usize {
+    let count = count.min(rows.len());
+    for row in rows.drain(..count) {
+        dead_letter(dead_letters, &row, reason);
+    }
+    count
+}
+
+// Owns the Postgres writes of a pipeline on a task of its own, so a slow or unreachable
+// database never holds up reading the feed or the other sinks
+pub struct PostgresWriter {
+    commands: mpsc::Sender<WriterCommand>,
+    task: Option<JoinHandle<()>>,
+    health_monitor: JoinHandle<()>,
+}
+
+impl PostgresWriter {
+    pub fn spawn(postgres: PumpPostgres, config: PostgresConfig, dead_letters: Option<DeadLetterStore>) -> Self {
+        let (commands, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let health_monitor = postgres.spawn_health_monitor(HEALTH_INTERVAL);
+        let state = WriterState {
+            postgres,
+            config,
+            dead_letters,
+            token_launches: Vec::new(),
+            trades: Vec::new(),
+            wallet_trades: Vec::new(),
+            migrations: Vec::new(),
+            candles: Vec::new(),
+            stats: PostgresWriteStats::default(),
+        };
+        Self {
+            commands,
+            task: Some(tokio::spawn(state.run(receiver))),
+            health_monitor,
+        }
+    }
+
+    // The sink that feeds events to this writer
+    pub fn sink(&self) -> PostgresSink {
+        PostgresSink {
+            commands: self.commands.clone(),
+        }
+    }
+
+    pub async fn upsert_bonding_curves(&self, states: Vec<BondingCurveState>) {
+        if self.commands.send(WriterCommand::BondingCurves(states)).await.is_err() {
+            warn!("Postgres writer has stopped; bonding curve snapshot not stored");
+        }
+    }
+
+    pub async fn push_dead_letter(&self, letter: DeadLetter) {
+        if self.commands.send(WriterCommand::DeadLetter(letter)).await.is_err() {
+            warn!("Postgres writer has stopped; dead letter kept in the file store only");
+        }
+    }
+
+    // Wait for everything queued to be written or dead-lettered; the writer stops afterwards
+    pub async fn finish(&mut self) {
+        let Some(task) = self.task.take() else {
+            return;
+        };
+        let (done, finished) = oneshot::channel();
+        if self.commands.send(WriterCommand::Finish(done)).await.is_ok() {
+            let _ = finished.await;
+        }
+        if let Err(e) = task.await {
+            warn!("Postgres writer failed: {:?}", e);
+        }
+        self.health_monitor.abort();
+    }
+}
+
+// Buffers rows per table and writes them with multi-row inserts
+struct WriterState {
+    postgres: PumpPostgres,
+    config: PostgresConfig,
+    // Where rows Postgres rejects, or that overflow the buffer during an outage, are kept
+    dead_letters: Option<DeadLetterStore>,
+    token_launches: Vec<TokenLaunch>,
+    trades: Vec<Trade>,
+    wallet_trades: Vec<WalletTrade>,
+    migrations: Vec<Migration>,
+    candles: Vec<Candle>,
+    stats: PostgresWriteStats,
+}
+
+impl WriterState {
+    async fn run(mut self, mut commands: mpsc::Receiver<WriterCommand>) {
+        let mut ticker = tokio::time::interval(Duration::from_millis(self.config.flush_interval_ms.max(1)));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                command = commands.recv() => match command {
+                    Some(WriterCommand::Event(event)) => self.buffer(*event).await,
+                    Some(WriterCommand::BondingCurves(states)) => self.upsert_bonding_curves(&states).await,
+                    Some(WriterCommand::DeadLetter(letter)) => self.push_dead_letter(&letter).await,
+                    Some(WriterCommand::Flush) => self.flush().await,
+                    Some(WriterCommand::Finish(done)) => {
+                        self.finish().await;
+                        let _ = done.send(());
+                        return;
+                    }
+                    None => {
+                        self.finish().await;
+                        return;
+                    }
+                },
+                _ = ticker.tick() => self.flush().await,
+            }
+        }
+    }
+
+    fn buffered(&self) -> usize {
+        self.token_launches.len()
+            + self.trades.len()
+            + self.wallet_trades.len()
+            + self.migrations.len()
+            + self.candles.len()
+    }
+
+    async fn buffer(&mut self, event: PumpEvent) {
+        match event {
+            PumpEvent::TokenLaunch(token_launch) => self.token_launches.push(token_launch),
+            PumpEvent::Trade(trade) => self.trades.push(trade),
+            PumpEvent::WalletTrade(wallet_trade) => self.wallet_trades.push(wallet_trade),
+            PumpEvent::Migration(migration) => self.migrations.push(migration),
+            PumpEvent::Candle(candle) => self.candles.push(candle),
+            PumpEvent::Unknown(_) => {}
+        }
+
+        let full = [
+            self.token_launches.len(),
+            self.trades.len(),
+            self.wallet_trades.len(),
+            self.migrations.len(),
+            self.candles.len(),
+        ]
+        .into_iter()
+        .any(|len| len >= self.config.batch_size);
+        if full && self.postgres.is_healthy() {
+            self.flush().await;
+        } else {
+            self.spill_overflow("postgres unavailable and buffer full");
+        }
+    }
+
+    // Each table is cleared once written, so a later table failing leaves only its own rows buffered
+    async fn insert_buffered(&mut self) -> Result<(u64, u64), String> {
+        let store = self.dead_letters.as_ref();
+        let mut totals = (0, 0);
+        let mut add = |(inserted, rejected)| {
+            totals.0 += inserted;
+            totals.1 += rejected;
+        };
+        add(insert_or_reject(&self.postgres, store, &self.token_launches).await?);
+        self.token_launches.clear();
+        add(insert_or_reject(&self.postgres, store, &self.trades).await?);
+        self.trades.clear();
+        add(insert_or_reject(&self.postgres, store, &self.wallet_trades).await?);
+        self.wallet_trades.clear();
+        add(insert_or_reject(&self.postgres, store, &self.migrations).await?);
+        self.migrations.clear();
+        add(insert_or_reject(&self.postgres, store, &self.candles).await?);
+        self.candles.clear();
+        Ok(totals)
+    }
+
+    // Past max_buffered_rows the oldest rows go to the dead-letter store
+    fn spill_overflow(&mut self, reason: &str) {
+        let excess = self.buffered().saturating_sub(self.config.max_buffered_rows);
+        if excess == 0 {
+            return;
+        }
+        let store = self.dead_letters.as_ref();
+        let mut left = excess;
+        left -= spill(store, &mut self.trades, left, reason);
+        left -= spill(store, &mut self.wallet_trades, left, reason);
+        left -= spill(store, &mut self.candles, left, reason);
+        left -= spill(store, &mut self.token_launches, left, reason);
+        spill(store, &mut self.migrations, left, reason);
+        warn!("Postgres buffer over {} rows; dead-lettered the oldest {}", self.config.max_buffered_rows, excess);
+    }
+
+    // While the database is marked unhealthy rows stay buffered and nothing is attempted
+    async fn flush(&mut self) {
+        let rows = self.buffered() as u64;
+        if rows == 0 || !self.postgres.is_healthy() {
+            return;
+        }
+
+        let started = Instant::now();
+        let (inserted, rejected) = match self.insert_buffered().await {
+            Ok(counts) => counts,
+            Err(e) => {
+                warn!("Postgres unreachable, keeping {} rows buffered: {}", self.buffered(), e);
+                self.postgres.mark_unhealthy();
+                self.spill_overflow("postgres unavailable and buffer full");
+                return;
+            }
+        };
+
+        let elapsed = started.elapsed();
+        self.stats.rows_written += inserted;
+        self.stats.rows_skipped += rows - inserted - rejected;
+        self.stats.rows_rejected += rejected;
+        self.stats.batches += 1;
+        self.stats.flush_time += elapsed;
+        if rejected > 0 {
+            warn!("Postgres rejected {} rows; they were dead-lettered", rejected);
+        }
+        info!(
+            "Postgres flush: {} rows ({} new) in {:?}, {} rows total at {:.0} rows/s",
+            rows,
+            inserted,
+            elapsed,
+            self.stats.rows_written,
+            self.stats.rows_per_sec()
+        );
+    }
+
+    // One last attempt even if the database looked down; whatever is left is dead-lettered
+    // rather than lost with the process
+    async fn finish(&mut self) {
+        if self.buffered() > 0 && !self.postgres.is_healthy() && self.postgres.health_check().await.is_ok() {
+            self.postgres.mark_healthy();
+        }
+        self.flush().await;
+        let left = self.buffered();
+        if left > 0 {
+            self.dead_letter_all("postgres unavailable at shutdown");
+            warn!("Postgres unavailable at shutdown; dead-lettered {} buffered rows", left);
+        }
+    }
+
+    fn dead_letter_all(&mut self, reason: &str) {
+        let store = self.dead_letters.as_ref();
+        spill(store, &mut self.token_launches, usize::MAX, reason);
+        spill(store, &mut self.trades, usize::MAX, reason);
+        spill(store, &mut self.wallet_trades, usize::MAX, reason);
+        spill(store, &mut self.migrations, usize::MAX, reason);
+        spill(store, &mut self.candles, usize::MAX, reason);
+    }
+
+    async fn upsert_bonding_curves(&self, states: &[BondingCurveState]) {
+        if !self.postgres.is_healthy() {
+            return;
+        }
+        if let Err(e) = self.postgres.upsert_bonding_curves(states).await {
+            warn!("Failed to upsert bonding curve snapshot: {:?}", e);
+            if is_connection_error(e.as_ref()) {
+                self.postgres.mark_unhealthy();
+            }
+        }
+    }
+
+    // Dead letters are already in the file store, so one that can't be inserted now is not retried
+    async fn push_dead_letter(&self, letter: &DeadLetter) {
+        if !self.postgres.is_healthy() {
+            return;
+        }
+        if let Err(e) = self.postgres.push_dead_letter(letter).await {
+            warn!("Failed to insert dead letter: {:?}", e);
+            if is_connection_error(e.as_ref()) {
+                self.postgres.mark_unhealthy();
+            }
+        }
+    }
+}
+
+// Hands events to the Postgres writer; the writer buffers and flushes them on its own schedule
+pub struct PostgresSink {
+    commands: mpsc::Sender<WriterCommand>,
+}
+
+#[async_trait(?Send)]
+impl EventSink for PostgresSink {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn write(&mut self, event: &PumpEvent) -> Result<(), Box<dyn Error>> {
+        if matches!(event, PumpEvent::Unknown(_)) {
+            return Ok(());
+        }
+        self.commands
+            .send(WriterCommand::Event(Box::new(event.clone())))
+            .await
+            .map_err(|_| "Postgres writer has stopped".into())
+    }
+
+    // Asks for a flush without waiting on it; PostgresWriter::finish waits for the last one
+    async fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.commands.try_send(WriterCommand::Flush) {
+            Err(mpsc::error::TrySendError::Closed(_)) => Err("Postgres writer has stopped".into()),
+            _ => Ok(()),
+        }
+    }
+}
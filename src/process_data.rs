@@ -1,164 +1,256 @@
 #![allow(non_snake_case)]
 #![allow(dead_code)]
 
-use serde_json::Value;
 use tracing::{info, warn};
-use crate::arrow::event_to_record_batch;
-use serde::{Deserialize};
-use crate::parquet_storage::ParquetStorage;
-use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, Utc};
+use crate::bonding_curve::{write_snapshot, BondingCurveTracker};
+use crate::candles::CandleBuilder;
+use crate::config::Config;
+use crate::dead_letter::{DeadLetter, DeadLetterConfig, DeadLetterStore};
+use crate::event::{Candle, PumpEvent, WalletTrade};
 use crate::postgres_db::PumpPostgres;
-
-#[derive(Debug, Deserialize)]
-
-pub enum PumpEvent {
-    TokenLaunch {
-        signature: String,
-        traderPublicKey: String,
-        txType: String,
-        mint: String,
-        solInPool: f64,
-        tokensInPool: f64,
-        initialBuy: f64,
-        solAmount: f64,
-        newTokenBalance: f64,
-        marketCapSol: f64,
-        name: String,
-        symbol: String,
-        uri: String,
-        pool: String,
-    },
-
-    Trade {
-        signature: String,
-        mint: String,
-        traderPublicKey: String,
-        txType: String,
-        tokenAmount: f64,
-        solAmount: f64,
-        newTokenBalance: f64,
-        bondingCurveKey: String,
-        vTokensInBondingCurve: f64,
-        vSolInBondingCurve: f64,
-        marketCapSol: f64,
-        pool: String,
-    },
-
-    Unknown,
-}
+use crate::postgres_writer::PostgresWriter;
+use crate::sink::{build_sinks, EventSink, SinkKind};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 
 pub struct PumpPipeline {
-    pub storage: ParquetStorage,
-    pub launch_buffer: Vec<RecordBatch>,
-    pub trade_buffer: Vec<RecordBatch>,
-    pub buffer_size: usize,
+    pub sinks: Vec<Box<dyn EventSink>>,
+    // Failed writes per sink, same order as sinks
+    pub sink_failures: Vec<u64>,
+    pub watched_wallets: HashSet<String>,
+    pub dead_letters: DeadLetterStore,
+    pub dead_letter_config: DeadLetterConfig,
+    pub postgres: Option<PumpPostgres>,
+    // Writes rows, curve snapshots and dead letters to Postgres off the ingest path
+    postgres_writer: Option<PostgresWriter>,
+    // None when candles are disabled
+    pub candles: Option<CandleBuilder>,
+    last_candle_sweep: Instant,
+    // None when bonding curve tracking is disabled
+    pub bonding_curves: Option<BondingCurveTracker>,
+    curve_snapshot_path: PathBuf,
+    curve_snapshot_interval: Duration,
+    last_curve_snapshot: Instant,
+    flush_interval: Duration,
 }
 
+const CANDLE_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
 impl PumpPipeline {
-   pub fn new(storage_path: &str, buffer_size: usize) -> Result<Self, Box<dyn std::error::Error>> {
-        let storage = ParquetStorage::new(storage_path.to_string())?;
-        
+   pub fn new(config: &Config, postgres: Option<PumpPostgres>) -> Result<Self, Box<dyn std::error::Error>> {
+        let dead_letters = DeadLetterStore::new(&config.storage_path)?;
+        let postgres_writer = match &postgres {
+            Some(postgres) => {
+                let rejected = config
+                    .dead_letters
+                    .enabled
+                    .then(|| DeadLetterStore::new(&config.storage_path))
+                    .transpose()?;
+                Some(PostgresWriter::spawn(postgres.clone(), config.postgres.clone(), rejected))
+            }
+            None => None,
+        };
+        let sinks = build_sinks(config, postgres_writer.as_ref())?;
+
             Ok(Self {
-            storage,
-            launch_buffer: Vec::new(),
-            trade_buffer: Vec::new(),
-            buffer_size,
+            sink_failures: vec![0; sinks.len()],
+            sinks,
+            watched_wallets: config.subscriptions.account_trades.iter().cloned().collect(),
+            dead_letters,
+            dead_letter_config: config.dead_letters.clone(),
+            postgres,
+            postgres_writer,
+            candles: config.candles.enabled.then(|| CandleBuilder::new(&config.candles)),
+            last_candle_sweep: Instant::now(),
+            bonding_curves: config
+                .bonding_curve
+                .enabled
+                .then(|| BondingCurveTracker::new(config.bonding_curve.clone())),
+            curve_snapshot_path: Path::new(&config.storage_path).join("bonding_curves.json"),
+            curve_snapshot_interval: Duration::from_secs(config.bonding_curve.snapshot_interval_secs),
+            last_curve_snapshot: Instant::now(),
+            flush_interval: Duration::from_secs(config.flush_interval_secs),
         })
     }
 
 
-pub async fn process_data(&mut self, text_json: Value) -> Result<(), Box<dyn std::error::Error>> {
-    let postgres = PumpPostgres::new().await.unwrap();
-    postgres.setup_tables().await.unwrap();
-     if let Some(tx_type_val) = text_json.get("txType") {
-    match tx_type_val.as_str() {
-        Some("create") => {
-            let token = PumpEvent::TokenLaunch {
-                signature: text_json["signature"].as_str().unwrap_or("").to_string(),
-                traderPublicKey: text_json["traderPublicKey"].as_str().unwrap_or("").to_string(),
-                txType: text_json["txType"].as_str().unwrap_or("").to_string(),
-                mint: text_json["mint"].as_str().unwrap_or("").to_string(),
-                solInPool: text_json["solInPool"].as_f64().unwrap_or(0.0),
-                tokensInPool: text_json["tokensInPool"].as_f64().unwrap_or(0.0),
-                initialBuy: text_json["initialBuy"].as_f64().unwrap_or(0.0),
-                solAmount: text_json["solAmount"].as_f64().unwrap_or(0.0),
-                newTokenBalance: text_json["newTokenBalance"].as_f64().unwrap_or(0.0),
-                marketCapSol: text_json["marketCapSol"].as_f64().unwrap_or(0.0),
-                name: text_json["name"].as_str().unwrap_or("").to_string(),
-                symbol: text_json["symbol"].as_str().unwrap_or("").to_string(),
-                uri: text_json["uri"].as_str().unwrap_or("").to_string(),
-                pool: text_json["pool"].as_str().unwrap_or("").to_string(),
-            };
-            println!("TokenLaunch: {:?}", token);
-            let batch = match event_to_record_batch(&token){
-                Ok(batch) => {
-                    println!("Converted to batch: {:?}", batch);
-                    batch},
-
-                Err(e) => {
-                    warn!("Failed to convert token launch to arrow batch: {:?}", e);
-                    return Err(e.into());
-                }
-            };
-            self.launch_buffer.push(batch);
-           postgres.push_token_launch(&text_json).await.unwrap();
+pub async fn process_data(&mut self, mut event: PumpEvent) -> Result<(), Box<dyn std::error::Error>> {
+    // Event time is the receive time, so replays rebuild the same curves and candles
+    let at = event.meta().map(|meta| meta.receivedAt).unwrap_or_else(Utc::now);
 
-            if self.launch_buffer.len() >= self.buffer_size {
-                self.storage.write_batch(&self.launch_buffer, "token_launch")?;
-                self.launch_buffer.clear();
-                println!("Pushed to launch buffer");
-            }
+    // Enrich before fan-out so wallet trades carry the same curve columns
+    if let Some(tracker) = self.bonding_curves.as_mut() {
+        match &mut event {
+            PumpEvent::Trade(trade) => tracker.apply_trade(trade, at),
+            PumpEvent::Migration(migration) => tracker.mark_graduated(&migration.mint),
+            _ => {}
         }
+    }
+    let mut events = vec![event];
 
-        Some("buy") | Some("sell") => {
-            let trade = PumpEvent::Trade {
-                signature: text_json["signature"].as_str().unwrap_or("").to_string(),
-                mint: text_json["mint"].as_str().unwrap_or("").to_string(),
-                traderPublicKey: text_json["traderPublicKey"].as_str().unwrap_or("").to_string(),
-                txType: text_json["txType"].as_str().unwrap_or("").to_string(),
-                tokenAmount: text_json["tokenAmount"].as_f64().unwrap_or(0.0),
-                solAmount: text_json["solAmount"].as_f64().unwrap_or(0.0),
-                newTokenBalance: text_json["newTokenBalance"].as_f64().unwrap_or(0.0),
-                bondingCurveKey: text_json["bondingCurveKey"].as_str().unwrap_or("").to_string(),
-                vTokensInBondingCurve: text_json["vTokensInBondingCurve"].as_f64().unwrap_or(0.0),
-                vSolInBondingCurve: text_json["vSolInBondingCurve"].as_f64().unwrap_or(0.0),
-                marketCapSol: text_json["marketCapSol"].as_f64().unwrap_or(0.0),
-                pool: text_json["pool"].as_str().unwrap_or("").to_string(),
+    match &events[0] {
+        // Trades by a watched wallet are also kept in their own dataset
+        PumpEvent::Trade(trade) if self.watched_wallets.contains(&trade.traderPublicKey) => {
+            let wallet_trade = WalletTrade {
+                watchedWallet: trade.traderPublicKey.clone(),
+                trade: trade.clone(),
             };
-            println!("Trade: {:?}", trade);
-            let batch = event_to_record_batch(&trade);
-            self.trade_buffer.push(batch?);
-            postgres.push_trade(&text_json).await.unwrap();
-
-            if self.trade_buffer.len() >= self.buffer_size {
-                self.storage.write_batch(&self.trade_buffer, "trade")?;
-                self.trade_buffer.clear();
-                println!("Pushed to trade buffer");
-            }
+            events.push(PumpEvent::WalletTrade(wallet_trade));
         }
 
+        PumpEvent::Unknown(unknown) => {
+            let reason = match &unknown.txType {
                 Some(other) => {
                     warn!(" Unhandled txType: {}", other);
+                    format!("unhandled txType: {}", other)
+                }
+                None => {
+                    info!(" Non-event/system message");
+                    "missing txType".to_string()
                 }
+            };
+            self.dead_letter(&unknown.raw.to_string(), &reason, at).await;
+            return Ok(());
+        }
+
+        _ => {}
+    }
+
+    if let Some(candles) = self.candles.as_mut() {
+        if let PumpEvent::Trade(trade) = &events[0] {
+            let closed = candles.on_trade(trade, at);
+            events.extend(closed.into_iter().map(PumpEvent::Candle));
+        }
+        if self.last_candle_sweep.elapsed() >= CANDLE_SWEEP_INTERVAL {
+            self.last_candle_sweep = Instant::now();
+            events.extend(candles.close_expired(at).into_iter().map(PumpEvent::Candle));
+        }
+    }
+
+    self.write_events(&events).await;
+
+    if self.last_curve_snapshot.elapsed() >= self.curve_snapshot_interval {
+        self.snapshot_bonding_curves().await;
+    }
+        Ok(())
+    }
+
+    pub fn flush_interval(&self) -> Duration {
+        self.flush_interval
+    }
+
+    // Periodic housekeeping for quiet feeds, where no event arrives to trigger it; called
+    // every flush_interval, so each tick flushes
+    pub async fn tick(&mut self) {
+        if let Err(e) = self.flush_sinks().await {
+            warn!("{}", e);
+        }
+        if self.last_curve_snapshot.elapsed() >= self.curve_snapshot_interval {
+            self.snapshot_bonding_curves().await;
+        }
+    }
+
+    // Publish the latest curve state per mint to a JSON file and, when connected, Postgres
+    async fn snapshot_bonding_curves(&mut self) {
+        self.last_curve_snapshot = Instant::now();
+        let Some(tracker) = self.bonding_curves.as_mut() else {
+            return;
+        };
+        let states = tracker.snapshot();
+
+        if let Err(e) = write_snapshot(&self.curve_snapshot_path, &states) {
+            warn!("Failed to write bonding curve snapshot: {:?}", e);
+        }
+        if let Some(writer) = &self.postgres_writer {
+            writer.upsert_bonding_curves(states).await;
+        }
+    }
+
+    // Write a finished candle straight to the sinks, bypassing the candle builder
+    pub async fn write_candle(&mut self, candle: Candle) {
+        self.write_events(&[PumpEvent::Candle(candle)]).await;
+    }
 
-                _ => {
-                    warn!("txType is not a string");
+    // A failing sink is logged and skipped so the others keep receiving events
+    async fn write_events(&mut self, events: &[PumpEvent]) {
+        for (sink, failures) in self.sinks.iter_mut().zip(self.sink_failures.iter_mut()) {
+            for event in events {
+                if let Err(e) = sink.write(event).await {
+                    *failures += 1;
+                    warn!("{} sink failed to write {} ({} failures): {:?}", sink.name(), event.event_type(), failures, e);
                 }
             }
-        } else {
-            info!(" Non-event/system message");
         }
+    }
+
+    // Keep a message we could not turn into an event so it can be reprocessed later
+    pub async fn dead_letter(&self, raw: &str, reason: &str, received_at: DateTime<Utc>) {
+        if !self.dead_letter_config.enabled {
+            return;
+        }
+
+        let letter = DeadLetter::new(raw, reason, received_at);
+        if let Err(e) = self.dead_letters.record(&letter) {
+            warn!("Failed to write dead letter: {:?}", e);
+        }
+
+        if self.dead_letter_config.postgres
+            && let Some(writer) = &self.postgres_writer
+        {
+            writer.push_dead_letter(letter).await;
+        }
+    }
+
+    // Connect to Postgres only when a sink or the dead-letter store writes to it
+    pub async fn connect_postgres(config: &Config) -> Result<Option<PumpPostgres>, Box<dyn std::error::Error>> {
+        let needed = config.sinks.enabled.contains(&SinkKind::Postgres) || config.dead_letters.postgres;
+        if !needed {
+            return Ok(None);
+        }
+        Ok(Some(PumpPostgres::new(&config.postgres).await?))
+    }
+
+    pub async fn flush_all(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Open candles are written as they stand so a shutdown does not lose them
+        if let Some(candles) = self.candles.as_mut() {
+            let open: Vec<PumpEvent> = candles.drain().into_iter().map(PumpEvent::Candle).collect();
+            self.write_events(&open).await;
+        }
+        self.snapshot_bonding_curves().await;
+
+        let flushed = self.flush_sinks().await;
+        // Wait for the Postgres writer to drain so nothing queued is lost with the process
+        if let Some(writer) = self.postgres_writer.as_mut() {
+            writer.finish().await;
+        }
+        flushed?;
+        eprintln!("Flushed all");
         Ok(())
     }
 
-    pub fn flush_all(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.storage.write_batch(&self.launch_buffer, "token_launch")?;
-        self.storage.write_batch(&self.trade_buffer, "trade")?;
-        self.launch_buffer.clear();
-        self.trade_buffer.clear();
-        println!("Flushed all");
+    // Every sink is flushed even if an earlier one fails
+    async fn flush_sinks(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut failed = Vec::new();
+        for sink in self.sinks.iter_mut() {
+            if let Err(e) = sink.flush().await {
+                warn!("{} sink failed to flush: {:?}", sink.name(), e);
+                failed.push(sink.name());
+            }
+        }
+        if !failed.is_empty() {
+            return Err(format!("Failed to flush sinks: {}", failed.join(", ")).into());
+        }
         Ok(())
     }
 
-}
\ No newline at end of file
+    // Close the Postgres pool once everything is flushed; sinks share the same pool
+    pub async fn close(&self) {
+        if let Some(postgres) = &self.postgres {
+            postgres.close().await;
+        }
+    }
+
+}
@@ -0,0 +1,200 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Connection, Executor, PgConnection, PgPool, Row};
+use std::error::Error;
+use std::time::Instant;
+use tracing::{info, warn};
+
+// Ordered SQL migrations compiled into the binary; append new ones, never edit applied ones
+pub struct SchemaMigration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MIGRATIONS: &[SchemaMigration] = &[
+    SchemaMigration {
+        version: 1,
+        name: "create_tables",
+        sql: include_str!("../migrations/0001_create_tables.sql"),
+    },
+    SchemaMigration {
+        version: 2,
+        name: "nullable_optional_columns",
+        sql: include_str!("../migrations/0002_nullable_optional_columns.sql"),
+    },
+    SchemaMigration {
+        version: 3,
+        name: "lookup_indexes",
+        sql: include_str!("../migrations/0003_lookup_indexes.sql"),
+    },
+    SchemaMigration {
+        version: 4,
+        name: "candles",
+        sql: include_str!("../migrations/0004_candles.sql"),
+    },
+    SchemaMigration {
+        version: 5,
+        name: "bonding_curve_state",
+        sql: include_str!("../migrations/0005_bonding_curve_state.sql"),
+    },
+    SchemaMigration {
+        version: 6,
+        name: "receipt_metadata",
+        sql: include_str!("../migrations/0006_receipt_metadata.sql"),
+    },
+    SchemaMigration {
+        version: 7,
+        name: "numeric_amounts",
+        sql: include_str!("../migrations/0007_numeric_amounts.sql"),
+    },
+    SchemaMigration {
+        version: 8,
+        name: "candle_trade_span",
+        sql: include_str!("../migrations/0008_candle_trade_span.sql"),
+    },
+    SchemaMigration {
+        version: 9,
+        name: "event_time",
+        sql: include_str!("../migrations/0009_event_time.sql"),
+    },
+    SchemaMigration {
+        version: 10,
+        name: "signature_received_at",
+        sql: include_str!("../migrations/0010_signature_received_at.sql"),
+    },
+];
+
+// Serializes concurrent `migrate up` runs against the same database
+const MIGRATION_LOCK_KEY: i64 = 0x7075_6d70_7472_6163;
+
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: DateTime<Utc>,
+}
+
+async fn ensure_tracking_table(conn: &mut PgConnection) -> Result<(), Box<dyn Error>> {
+    sqlx::query("
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version BIGINT PRIMARY KEY,
+            name TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            execution_ms BIGINT NOT NULL,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )")
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn applied_migrations(conn: &mut PgConnection) -> Result<Vec<AppliedMigration>, Box<dyn Error>> {
+    ensure_tracking_table(conn).await?;
+    let rows = sqlx::query("SELECT version, name, checksum, applied_at FROM schema_migrations ORDER BY version")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut applied = Vec::with_capacity(rows.len());
+    for row in rows {
+        applied.push(AppliedMigration {
+            version: row.try_get("version")?,
+            name: row.try_get("name")?,
+            checksum: row.try_get("checksum")?,
+            applied_at: row.try_get("applied_at")?,
+        });
+    }
+    Ok(applied)
+}
+
+// md5 computed by Postgres so checksums match what is stored in schema_migrations
+async fn checksum(conn: &mut PgConnection, sql: &str) -> Result<String, Box<dyn Error>> {
+    Ok(sqlx::query_scalar("SELECT md5($1)").bind(sql).fetch_one(conn).await?)
+}
+
+// Apply every pending migration, each in its own transaction; returns how many ran.
+// Everything runs on the connection holding the lock, so a pool of one is enough
+pub async fn migrate_up(pool: &PgPool) -> Result<usize, Box<dyn Error>> {
+    let mut conn = pool.acquire().await?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+
+    let result = apply_pending(&mut conn).await;
+
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+    result
+}
+
+async fn apply_pending(conn: &mut PgConnection) -> Result<usize, Box<dyn Error>> {
+    // Read under the lock so a concurrent run's migrations are seen as applied
+    let applied = applied_migrations(conn).await?;
+    let mut count = 0;
+
+    for migration in MIGRATIONS {
+        let checksum = checksum(conn, migration.sql).await?;
+        if let Some(existing) = applied.iter().find(|a| a.version == migration.version) {
+            if existing.checksum != checksum {
+                warn!(
+                    "Migration {} ({}) changed since it was applied; not re-running it",
+                    migration.version, migration.name
+                );
+            }
+            continue;
+        }
+
+        let started = Instant::now();
+        let mut tx = conn.begin().await?;
+        tx.execute(migration.sql)
+            .await
+            .map_err(|e| format!("Migration {} ({}) failed: {}", migration.version, migration.name, e))?;
+        sqlx::query("INSERT INTO schema_migrations (version, name, checksum, execution_ms) VALUES ($1, $2, $3, $4)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(&checksum)
+            .bind(started.elapsed().as_millis() as i64)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        info!("Applied migration {} ({}) in {:?}", migration.version, migration.name, started.elapsed());
+        count += 1;
+    }
+
+    if let Some(unknown) = applied.iter().find(|a| !MIGRATIONS.iter().any(|m| m.version == a.version)) {
+        warn!(
+            "Database has migration {} ({}) that this build does not know about",
+            unknown.version, unknown.name
+        );
+    }
+    Ok(count)
+}
+
+pub async fn print_status(pool: &PgPool) -> Result<(), Box<dyn Error>> {
+    let mut conn = pool.acquire().await?;
+    let applied = applied_migrations(&mut conn).await?;
+
+    for migration in MIGRATIONS {
+        let state = match applied.iter().find(|a| a.version == migration.version) {
+            Some(existing) if existing.checksum != checksum(&mut conn, migration.sql).await? => {
+                format!("applied {} (modified since)", existing.applied_at.format("%Y-%m-%d %H:%M:%S"))
+            }
+            Some(existing) => format!("applied {}", existing.applied_at.format("%Y-%m-%d %H:%M:%S")),
+            None => "pending".to_string(),
+        };
+        println!("{:>4}  {:<30} {}", migration.version, migration.name, state);
+    }
+    for unknown in applied.iter().filter(|a| !MIGRATIONS.iter().any(|m| m.version == a.version)) {
+        println!("{:>4}  {:<30} applied but unknown to this build", unknown.version, unknown.name);
+    }
+
+    let pending = MIGRATIONS
+        .iter()
+        .filter(|m| !applied.iter().any(|a| a.version == m.version))
+        .count();
+    println!("{} applied, {} pending", applied.len(), pending);
+    Ok(())
+}
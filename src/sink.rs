@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::config::Config;
+use crate::event::PumpEvent;
+use crate::parquet_storage::ParquetSink;
+use crate::postgres_writer::PostgresWriter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkKind {
+    /// Parquet files under storage_path
+    Parquet,
+    /// Postgres tables
+    Postgres,
+    /// One JSON object per line in a file
+    Jsonl,
+    /// One JSON object per line on stdout
+    Stdout,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SinkConfig {
+    pub enabled: Vec<SinkKind>,
+    // Defaults to <storage_path>/events.jsonl
+    pub jsonl_path: Option<PathBuf>,
+}
+
+impl Default for SinkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: vec![SinkKind::Parquet, SinkKind::Postgres],
+            jsonl_path: None,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+pub trait EventSink {
+    fn name(&self) -> &'static str;
+
+    async fn write(&mut self, event: &PumpEvent) -> Result<(), Box<dyn Error>>;
+
+    // Persist anything still buffered
+    async fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+pub fn build_sinks(config: &Config, postgres: Option<&PostgresWriter>) -> Result<Vec<Box<dyn EventSink>>, Box<dyn Error>> {
+    let mut sinks: Vec<Box<dyn EventSink>> = Vec::new();
+
+    for kind in &config.sinks.enabled {
+        let sink: Box<dyn EventSink> = match kind {
+            SinkKind::Parquet => Box::new(ParquetSink::new(&config.storage_path, config.buffer_size, &config.parquet)?),
+            SinkKind::Postgres => {
+                let postgres = postgres.ok_or("postgres sink enabled without a database connection")?;
+                Box::new(postgres.sink())
+            }
+            SinkKind::Jsonl => {
+                let path = config
+                    .sinks
+                    .jsonl_path
+                    .clone()
+                    .unwrap_or_else(|| Path::new(&config.storage_path).join("events.jsonl"));
+                Box::new(JsonlSink::new(&path)?)
+            }
+            SinkKind::Stdout => Box::new(StdoutSink),
+        };
+        info!("Enabled {} sink", sink.name());
+        sinks.push(sink);
+    }
+
+    Ok(sinks)
+}
+
+pub struct JsonlSink {
+    writer: BufWriter<File>,
+}
+
+impl JsonlSink {
+    pub fn new(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl EventSink for JsonlSink {
+    fn name(&self) -> &'static str {
+        "jsonl"
+    }
+
+    async fn write(&mut self, event: &PumpEvent) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+pub struct StdoutSink;
+
+#[async_trait(?Send)]
+impl EventSink for StdoutSink {
+    fn name(&self) -> &'static str {
+        "stdout"
+    }
+
+    async fn write(&mut self, event: &PumpEvent) -> Result<(), Box<dyn Error>> {
+        println!("{}", serde_json::to_string(event)?);
+        Ok(())
+    }
+}
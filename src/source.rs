@@ -0,0 +1,434 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use futures::{SinkExt, StreamExt};
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader, Lines};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{accept_async, connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{info, warn};
+use url::Url;
+
+use crate::config::Config;
+use crate::subscriptions::{subscribe_token_trade, unsubscribe_token_trade, SubscriptionConfig};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    /// Live PumpPortal websocket
+    Websocket,
+    /// JSONL file of raw messages
+    File,
+    /// Raw messages on stdin, one per line
+    Stdin,
+    /// Local websocket server replaying a JSONL file
+    Mock,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SourceConfig {
+    pub kind: SourceKind,
+    // Input for the file and mock sources
+    pub path: Option<PathBuf>,
+    pub mock_port: u16,
+    pub mock_interval_ms: u64,
+    // Replay the file to every connection instead of serving it once
+    pub mock_loop: bool,
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        Self {
+            kind: SourceKind::Websocket,
+            path: None,
+            mock_port: 8765,
+            mock_interval_ms: 100,
+            mock_loop: false,
+        }
+    }
+}
+
+// A raw message as it arrived, before any parsing
+#[derive(Debug, Clone)]
+pub struct RawEvent {
+    pub received_at: DateTime<Utc>,
+    pub text: String,
+}
+
+impl RawEvent {
+    pub fn now(text: String) -> Self {
+        Self {
+            received_at: Utc::now(),
+            text,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+pub trait EventSource {
+    // Next raw message, or None once the source is exhausted. Must be cancel safe:
+    // ingest drops it whenever the flush timer or a shutdown signal fires first
+    async fn next_event(&mut self) -> Option<RawEvent>;
+
+    // Dynamic trade subscriptions; only live feeds act on these
+    async fn subscribe_token_trades(&mut self, _mints: &[String]) {}
+
+    async fn unsubscribe_token_trades(&mut self, _mints: &[String]) {}
+
+    // Live sources are journaled, recorded inputs are not
+    fn is_live(&self) -> bool {
+        true
+    }
+}
+
+pub async fn build_source(config: &Config) -> Result<Box<dyn EventSource>, Box<dyn Error>> {
+    let source: Box<dyn EventSource> = match config.source.kind {
+        SourceKind::Websocket => Box::new(WebsocketSource::new(
+            Url::parse(&config.ws_url)?,
+            config.subscriptions.clone(),
+        )),
+        SourceKind::File => {
+            let path = config.source.path.as_ref().ok_or("source.path is required for the file source")?;
+            let file = tokio::fs::File::open(path)
+                .await
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            Box::new(LineSource::new(file))
+        }
+        SourceKind::Stdin => Box::new(LineSource::new(tokio::io::stdin())),
+        SourceKind::Mock => {
+            let path = config.source.path.as_ref().ok_or("source.path is required for the mock source")?;
+            let addr = spawn_mock_server(
+                path.clone(),
+                config.source.mock_port,
+                Duration::from_millis(config.source.mock_interval_ms),
+                config.source.mock_loop,
+            )
+            .await?;
+            Box::new(WebsocketSource::mock(
+                Url::parse(&format!("ws://{}", addr))?,
+                config.subscriptions.clone(),
+            ))
+        }
+    };
+    Ok(source)
+}
+
+// Jittered exponential backoff between reconnect attempts
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self { initial, max, attempt: 0 }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let exp = self.initial.saturating_mul(2u32.saturating_pow(self.attempt));
+        let capped = exp.min(self.max);
+        self.attempt = self.attempt.saturating_add(1);
+
+        // Full jitter: pick uniformly between half the capped delay and the capped delay
+        let capped_ms = capped.as_millis() as u64;
+        let jittered = rand::thread_rng().gen_range(capped_ms / 2..=capped_ms);
+        Duration::from_millis(jittered)
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+// Connection health counters, logged after every reconnect
+#[derive(Debug, Default)]
+pub struct ReconnectStats {
+    pub disconnects: u64,
+    pub failed_connects: u64,
+    pub total_gap: Duration,
+    pub longest_gap: Duration,
+    pub last_gap: Duration,
+}
+
+impl ReconnectStats {
+    pub fn record_gap(&mut self, gap: Duration) {
+        self.last_gap = gap;
+        self.total_gap += gap;
+        if gap > self.longest_gap {
+            self.longest_gap = gap;
+        }
+    }
+}
+
+// PumpPortal-style websocket that reconnects and resubscribes on its own
+pub struct WebsocketSource {
+    url: Url,
+    subscriptions: SubscriptionConfig,
+    // Trade subscriptions added at runtime, replayed on reconnect
+    followed: HashSet<String>,
+    backoff: Backoff,
+    stats: ReconnectStats,
+    disconnected_at: Option<Instant>,
+    // Next connect attempt waits until then; kept on the source so a cancelled wait resumes it
+    reconnect_at: Option<tokio::time::Instant>,
+    stream: Option<WsStream>,
+    // False for the mock server, whose frames are already on disk
+    live: bool,
+}
+
+impl WebsocketSource {
+    pub fn new(url: Url, subscriptions: SubscriptionConfig) -> Self {
+        Self {
+            url,
+            subscriptions,
+            followed: HashSet::new(),
+            backoff: Backoff::new(Duration::from_millis(500), Duration::from_secs(60)),
+            stats: ReconnectStats::default(),
+            disconnected_at: None,
+            reconnect_at: None,
+            stream: None,
+            live: true,
+        }
+    }
+
+    pub fn mock(url: Url, subscriptions: SubscriptionConfig) -> Self {
+        Self {
+            live: false,
+            ..Self::new(url, subscriptions)
+        }
+    }
+
+    async fn connect_and_subscribe(&self) -> Result<WsStream, Box<dyn Error>> {
+        let (mut ws_stream, _) = connect_async(self.url.clone()).await?;
+
+        // Subscriptions are per-connection, so they are replayed on every reconnect
+        let mut sub_msgs = self.subscriptions.messages();
+        if !self.followed.is_empty() {
+            let followed: Vec<String> = self.followed.iter().cloned().collect();
+            sub_msgs.push(subscribe_token_trade(&followed));
+        }
+        for sub_msg in sub_msgs {
+            ws_stream.send(Message::Text(sub_msg.to_string())).await?;
+        }
+
+        Ok(ws_stream)
+    }
+
+    async fn connect(&mut self) {
+        loop {
+            if let Some(at) = self.reconnect_at {
+                tokio::time::sleep_until(at).await;
+                self.reconnect_at = None;
+            }
+            match self.connect_and_subscribe().await {
+                Ok(ws_stream) => {
+                    self.stream = Some(ws_stream);
+                    break;
+                }
+                Err(e) => {
+                    self.stats.failed_connects += 1;
+                    let delay = self.backoff.next_delay();
+                    warn!("Failed to connect to {}: {:?}, retrying in {:?}", self.url, e, delay);
+                    self.reconnect_at = Some(tokio::time::Instant::now() + delay);
+                }
+            }
+        }
+
+        self.backoff.reset();
+        if let Some(since) = self.disconnected_at.take() {
+            self.stats.record_gap(since.elapsed());
+            info!("Reconnected after {:?} gap, stats: {:?}", self.stats.last_gap, self.stats);
+        } else {
+            info!("Connected to {}", self.url);
+        }
+    }
+
+    fn disconnected(&mut self) {
+        self.stream = None;
+        self.stats.disconnects += 1;
+        self.disconnected_at = Some(Instant::now());
+        let delay = self.backoff.next_delay();
+        warn!("Disconnected from {} (disconnects: {}), reconnecting in {:?}", self.url, self.stats.disconnects, delay);
+        self.reconnect_at = Some(tokio::time::Instant::now() + delay);
+    }
+
+    async fn send(&mut self, msg: serde_json::Value) {
+        let Some(stream) = self.stream.as_mut() else {
+            // Picked up from `followed` on the next connect
+            return;
+        };
+        if let Err(e) = stream.send(Message::Text(msg.to_string())).await {
+            warn!("Failed to send subscription update: {:?}", e);
+            self.disconnected();
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl EventSource for WebsocketSource {
+    async fn next_event(&mut self) -> Option<RawEvent> {
+        loop {
+            if self.stream.is_none() {
+                self.connect().await;
+            }
+            let stream = self.stream.as_mut()?;
+
+            match stream.next().await {
+                Some(Ok(Message::Text(text))) => return Some(RawEvent::now(text)),
+                Some(Ok(Message::Close(frame))) => {
+                    warn!("Websocket closed by server: {:?}", frame);
+                    self.disconnected();
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => {
+                    warn!("Websocket error: {:?}", e);
+                    self.disconnected();
+                }
+                None => self.disconnected(),
+            }
+        }
+    }
+
+    async fn subscribe_token_trades(&mut self, mints: &[String]) {
+        self.followed.extend(mints.iter().cloned());
+        self.send(subscribe_token_trade(mints)).await;
+    }
+
+    async fn unsubscribe_token_trades(&mut self, mints: &[String]) {
+        for mint in mints {
+            self.followed.remove(mint);
+        }
+        self.send(unsubscribe_token_trade(mints)).await;
+    }
+
+    fn is_live(&self) -> bool {
+        self.live
+    }
+}
+
+// One raw message per line, from a file or stdin
+pub struct LineSource {
+    lines: Lines<BufReader<Box<dyn AsyncRead + Unpin>>>,
+}
+
+impl LineSource {
+    pub fn new(reader: impl AsyncRead + Unpin + 'static) -> Self {
+        let reader: Box<dyn AsyncRead + Unpin> = Box::new(reader);
+        Self {
+            lines: BufReader::new(reader).lines(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl EventSource for LineSource {
+    async fn next_event(&mut self) -> Option<RawEvent> {
+        loop {
+            match self.lines.next_line().await {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => return Some(RawEvent::now(line)),
+                Ok(None) => return None,
+                Err(e) => {
+                    warn!("Failed to read input line: {:?}", e);
+                    return None;
+                }
+            }
+        }
+    }
+
+    fn is_live(&self) -> bool {
+        false
+    }
+}
+
+// Serve the lines of a JSONL file over websocket, then close. Without looping the file is
+// served once: a client that reconnects mid-way picks up where it left off, and once every
+// frame is sent the server stops. With looping each connection gets the whole file again,
+// exercising the client's reconnect path
+pub async fn spawn_mock_server(
+    path: PathBuf,
+    port: u16,
+    interval: Duration,
+    looping: bool,
+) -> Result<SocketAddr, Box<dyn Error>> {
+    let contents = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let frames: Vec<String> = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    let addr = listener.local_addr()?;
+    info!("Mock websocket server serving {} frames on ws://{}", frames.len(), addr);
+
+    let frames = Arc::new(frames);
+    // Frames served so far when not looping
+    let served = Arc::new(AtomicUsize::new(0));
+    tokio::spawn(async move {
+        loop {
+            let (tcp, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("Mock server accept failed: {:?}", e);
+                    continue;
+                }
+            };
+            if !looping && served.load(Ordering::Relaxed) >= frames.len() {
+                info!("Mock server has served every frame; no longer accepting connections");
+                return;
+            }
+            let frames = frames.clone();
+            let served = served.clone();
+
+            tokio::spawn(async move {
+                let ws_stream = match accept_async(tcp).await {
+                    Ok(ws_stream) => ws_stream,
+                    Err(e) => {
+                        warn!("Mock server handshake with {} failed: {:?}", peer, e);
+                        return;
+                    }
+                };
+                let (mut write, mut read) = ws_stream.split();
+
+                // Log subscription requests from the client
+                tokio::spawn(async move {
+                    while let Some(Ok(msg)) = read.next().await {
+                        if let Message::Text(text) = msg {
+                            info!("Mock server received: {}", text);
+                        }
+                    }
+                });
+
+                let start = if looping { 0 } else { served.load(Ordering::Relaxed) };
+                for frame in &frames[start..] {
+                    if write.send(Message::Text(frame.clone())).await.is_err() {
+                        return;
+                    }
+                    if !looping {
+                        served.fetch_add(1, Ordering::Relaxed);
+                    }
+                    tokio::time::sleep(interval).await;
+                }
+                let _ = write.send(Message::Close(None)).await;
+            });
+        }
+    });
+
+    Ok(addr)
+}
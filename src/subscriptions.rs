@@ -0,0 +1,62 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+// Which PumpPortal data streams to subscribe to
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SubscriptionConfig {
+    pub new_tokens: bool,
+    pub token_trades: Vec<String>,
+    pub account_trades: Vec<String>,
+    pub migrations: bool,
+}
+
+impl Default for SubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            new_tokens: true,
+            token_trades: Vec::new(),
+            account_trades: Vec::new(),
+            migrations: false,
+        }
+    }
+}
+
+impl SubscriptionConfig {
+    // Messages sent after every (re)connect
+    pub fn messages(&self) -> Vec<Value> {
+        let mut messages = Vec::new();
+
+        if self.new_tokens {
+            messages.push(json!({ "method": "subscribeNewToken" }));
+        }
+        if !self.token_trades.is_empty() {
+            messages.push(subscribe_token_trade(&self.token_trades));
+        }
+        if !self.account_trades.is_empty() {
+            messages.push(json!({
+                "method": "subscribeAccountTrade",
+                "keys": self.account_trades,
+            }));
+        }
+        if self.migrations {
+            messages.push(json!({ "method": "subscribeMigration" }));
+        }
+
+        messages
+    }
+}
+
+pub fn subscribe_token_trade(mints: &[String]) -> Value {
+    json!({
+        "method": "subscribeTokenTrade",
+        "keys": mints,
+    })
+}
+
+pub fn unsubscribe_token_trade(mints: &[String]) -> Value {
+    json!({
+        "method": "unsubscribeTokenTrade",
+        "keys": mints,
+    })
+}